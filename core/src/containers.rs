@@ -0,0 +1,1035 @@
+use std::{
+    alloc::Layout,
+    ops::{Index, IndexMut, Range},
+    ptr::null,
+};
+
+#[derive(Debug)]
+#[repr(C)]
+pub struct CppVector<T> {
+    start: *mut T,
+    end: *mut T,
+    eos: *mut T,
+}
+
+impl<T> Default for CppVector<T> {
+    fn default() -> Self {
+        CppVector::new()
+    }
+}
+
+impl<T> CppVector<T> {
+    /// Zero sized types would make the length and capacity calculations divide by zero, so they are rejected at
+    /// compile time
+    const IS_NOT_ZST: () = assert!(std::mem::size_of::<T>() != 0, "CppVector does not support zero sized types");
+
+    /// Reallocates the backing buffer to hold at least `min_capacity` elements, growing geometrically
+    unsafe fn realloc(&mut self, min_capacity: usize) {
+        let current_capacity = self.capacity();
+        let current_len = self.len();
+
+        let new_capacity = min_capacity.max(current_capacity * 2).max(4);
+        let layout = Layout::array::<T>(new_capacity).unwrap();
+        let new_start = std::alloc::alloc(layout) as *mut T;
+
+        if new_start.is_null() {
+            std::alloc::handle_alloc_error(layout);
+        }
+
+        if !self.start.is_null() {
+            std::ptr::copy_nonoverlapping(self.start, new_start, current_len);
+            self.dealloc();
+        }
+
+        self.start = new_start;
+        self.end = self.start.add(current_len);
+        self.eos = self.start.add(new_capacity);
+    }
+
+    /// Frees the backing buffer without dropping any of the elements
+    unsafe fn dealloc(&mut self) {
+        if !self.start.is_null() {
+            let layout = Layout::array::<T>(self.capacity()).unwrap();
+            std::alloc::dealloc(self.start as _, layout);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    pub fn new() -> Self {
+        let () = Self::IS_NOT_ZST;
+        Self {
+            start: null::<T>() as _,
+            end: null::<T>() as _,
+            eos: null::<T>() as _,
+        }
+    }
+
+    pub fn with_capacity(cap: usize) -> Self {
+        let mut vec = Self::new();
+        if cap != 0 {
+            unsafe {
+                vec.realloc(cap);
+            }
+        }
+        vec
+    }
+
+    pub fn push(&mut self, val: T) {
+        self.reserve(1);
+        unsafe {
+            self.end.write(val);
+            self.end = self.end.add(1);
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+
+        unsafe {
+            self.end = self.end.sub(1);
+            Some(self.end.read())
+        }
+    }
+
+    /// Inserts the value at the provided index, shifting everything after it to the right
+    pub fn insert(&mut self, index: usize, val: T) {
+        let len = self.len();
+        assert!(index <= len, "insertion index (is {}) should be <= len (is {})", index, len);
+
+        self.reserve(1);
+        unsafe {
+            let ptr = self.start.add(index);
+            std::ptr::copy(ptr, ptr.add(1), len - index);
+            ptr.write(val);
+            self.end = self.end.add(1);
+        }
+    }
+
+    /// Removes the value at the provided index, shifting everything after it to the left
+    pub fn remove(&mut self, index: usize) -> T {
+        let len = self.len();
+        assert!(index < len, "removal index (is {}) should be < len (is {})", index, len);
+
+        unsafe {
+            let ptr = self.start.add(index);
+            let val = ptr.read();
+            std::ptr::copy(ptr.add(1), ptr, len - index - 1);
+            self.end = self.end.sub(1);
+            val
+        }
+    }
+
+    /// Retains only the elements for which the predicate returns true, preserving their order
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let len = self.len();
+        let mut kept = 0;
+
+        unsafe {
+            // Shorten the vector first so that a panicking predicate only leaks, instead of double dropping
+            self.end = self.start;
+
+            for index in 0..len {
+                let ptr = self.start.add(index);
+                if f(&*ptr) {
+                    if index != kept {
+                        std::ptr::copy_nonoverlapping(ptr, self.start.add(kept), 1);
+                    }
+                    kept += 1;
+                } else {
+                    std::ptr::drop_in_place(ptr);
+                }
+            }
+
+            self.end = self.start.add(kept);
+        }
+    }
+
+    /// Shortens the vector to the provided length, dropping the rest of the elements
+    pub fn truncate(&mut self, len: usize) {
+        let current_len = self.len();
+        if len >= current_len {
+            return;
+        }
+
+        unsafe {
+            let tail = std::ptr::slice_from_raw_parts_mut(self.start.add(len), current_len - len);
+            self.end = self.start.add(len);
+            std::ptr::drop_in_place(tail);
+        }
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        let required = self.len() + additional;
+        if required > self.capacity() {
+            unsafe {
+                self.realloc(required);
+            }
+        }
+    }
+
+    pub fn iter(&self) -> CppVectorIterator<'_, T> {
+        self.into_iter()
+    }
+
+    pub fn iter_mut(&mut self) -> CppVectorIteratorMut<'_, T> {
+        self.into_iter()
+    }
+
+    pub fn len(&self) -> usize {
+        if self.start.is_null() {
+            return 0;
+        }
+
+        ((self.end as usize) - (self.start as usize)) / std::mem::size_of::<T>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        if self.start.is_null() {
+            return 0;
+        }
+
+        ((self.eos as usize) - (self.start as usize)) / std::mem::size_of::<T>()
+    }
+
+    pub fn as_ptr(&self) -> *const T {
+        self.start
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.start
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        if self.start.is_null() {
+            return &[];
+        }
+
+        unsafe { std::slice::from_raw_parts(self.start, self.len()) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        if self.start.is_null() {
+            return &mut [];
+        }
+
+        unsafe { std::slice::from_raw_parts_mut(self.start, self.len()) }
+    }
+
+    pub fn extend_from_slice(&mut self, slice: &[T])
+    where
+        T: Copy + Clone,
+    {
+        self.reserve(slice.len());
+        unsafe {
+            std::ptr::copy_nonoverlapping(slice.as_ptr(), self.end, slice.len());
+            self.end = self.end.add(slice.len());
+        }
+    }
+}
+
+impl<T: Copy + Clone> CppVector<T> {
+    pub fn from_slice(slice: &[T]) -> Self {
+        let mut vec = Self::with_capacity(slice.len());
+        vec.extend_from_slice(slice);
+        vec
+    }
+}
+
+impl<T: Clone> CppVector<T> {
+    pub fn clone_from_slice(slice: &[T]) -> Self {
+        let mut vec = Self::with_capacity(slice.len());
+        for val in slice {
+            vec.push(val.clone());
+        }
+        vec
+    }
+}
+
+impl<T: Clone> Clone for CppVector<T> {
+    fn clone(&self) -> Self {
+        Self::clone_from_slice(self.as_slice())
+    }
+}
+
+/// Dropping a `CppVector` drops its elements and frees its buffer, so this should only ever happen for vectors that
+/// were created on the Rust side. The game's structs wrap their vectors in `ManuallyDrop` so that they never get freed.
+impl<T> Drop for CppVector<T> {
+    fn drop(&mut self) {
+        self.clear();
+        unsafe {
+            self.dealloc();
+        }
+    }
+}
+
+impl<T> Extend<T> for CppVector<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for val in iter {
+            self.push(val);
+        }
+    }
+}
+
+impl<T> Index<usize> for CppVector<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.as_slice()[index]
+    }
+}
+
+impl<T> Index<Range<usize>> for CppVector<T> {
+    type Output = [T];
+
+    fn index(&self, index: Range<usize>) -> &Self::Output {
+        &self.as_slice()[index]
+    }
+}
+
+impl<T> IndexMut<usize> for CppVector<T> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.as_mut_slice()[index]
+    }
+}
+
+impl<T> IndexMut<Range<usize>> for CppVector<T> {
+    fn index_mut(&mut self, index: Range<usize>) -> &mut Self::Output {
+        &mut self.as_mut_slice()[index]
+    }
+}
+
+impl<'a, T> IntoIterator for &'a CppVector<T> {
+    type IntoIter = CppVectorIterator<'a, T>;
+    type Item = &'a T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        CppVectorIterator { vector: self, index: 0 }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut CppVector<T> {
+    type IntoIter = CppVectorIteratorMut<'a, T>;
+    type Item = &'a mut T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        CppVectorIteratorMut { vector: self, index: 0 }
+    }
+}
+
+pub struct CppVectorIterator<'a, T> {
+    vector: &'a CppVector<T>,
+    index: isize,
+}
+
+impl<'a, T> Iterator for CppVectorIterator<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        unsafe {
+            if (self.index as usize) < self.vector.len() {
+                self.index += 1;
+                Some(&*self.vector.start.offset(self.index - 1))
+            } else {
+                None
+            }
+        }
+    }
+}
+
+pub struct CppVectorIteratorMut<'a, T> {
+    vector: &'a mut CppVector<T>,
+    index: isize,
+}
+
+impl<'a, T> Iterator for CppVectorIteratorMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        unsafe {
+            if (self.index as usize) < self.vector.len() {
+                self.index += 1;
+                Some(&mut *self.vector.start.offset(self.index - 1))
+            } else {
+                None
+            }
+        }
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug, Copy, Clone)]
+pub enum LoadType {
+    Directory = 0x0,
+    File = 0x1,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct LoadInfo {
+    pub ty: LoadType,
+    pub filepath_index: u32,
+    pub directory_index: u32,
+    pub files_to_load: u32,
+}
+
+impl LoadInfo {
+    /// Checks if this is a file load for the provided file path index
+    pub fn is_file(&self, filepath_index: u32) -> bool {
+        matches!(self.ty, LoadType::File) && self.filepath_index == filepath_index
+    }
+
+    /// Checks if both of these entries would load the same thing
+    pub fn is_same_load(&self, other: &LoadInfo) -> bool {
+        match (self.ty, other.ty) {
+            (LoadType::File, LoadType::File) => self.filepath_index == other.filepath_index,
+            (LoadType::Directory, LoadType::Directory) => {
+                self.directory_index == other.directory_index
+            }
+            _ => false,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct ListNode {
+    pub next: *mut ListNode,
+    pub prev: *mut ListNode,
+    pub data: LoadInfo,
+}
+
+/// Allocates a node with the game's allocator, since the game frees the nodes once it has loaded them
+#[cfg(target_os = "switch")]
+unsafe fn alloc_node() -> *mut ListNode {
+    skyline::libc::malloc(std::mem::size_of::<ListNode>()) as *mut ListNode
+}
+
+#[cfg(target_os = "switch")]
+unsafe fn free_node(node: *mut ListNode) {
+    skyline::libc::free(node as _);
+}
+
+/// The host (the tests and Miri) doesn't have the game's allocator, so the nodes are allocated with the global
+/// allocator instead
+#[cfg(not(target_os = "switch"))]
+unsafe fn alloc_node() -> *mut ListNode {
+    std::alloc::alloc(Layout::new::<ListNode>()) as *mut ListNode
+}
+
+#[cfg(not(target_os = "switch"))]
+unsafe fn free_node(node: *mut ListNode) {
+    std::alloc::dealloc(node as _, Layout::new::<ListNode>());
+}
+
+impl<'a> IntoIterator for &'a ResList {
+    type IntoIter = ResListIter<'a>;
+    type Item = &'a LoadInfo;
+
+    fn into_iter(self) -> Self::IntoIter {
+        ResListIter { list: self, count: 0 }
+    }
+}
+
+impl<'a> IntoIterator for &'a mut ResList {
+    type IntoIter = ResListIterMut<'a>;
+    type Item = &'a mut LoadInfo;
+
+    fn into_iter(self) -> Self::IntoIter {
+        ResListIterMut { list: self, count: 0 }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct ResList {
+    pub size: usize,
+    pub next: *mut ListNode,
+    pub end: *mut ListNode,
+}
+
+impl ResList {
+    /// Gets the sentinel node of the list.
+    ///
+    /// The list is circular, with the `next` and `end` fields acting as the `next` and `prev` fields of a node that
+    /// marks both the start and the end of the list. The pointer is only ever stored in the nodes and compared against,
+    /// see [`ResList::next_field`].
+    fn sentinel(&mut self) -> *mut ListNode {
+        let list = self as *mut ResList;
+        unsafe { std::ptr::addr_of_mut!((*list).next) as *mut ListNode }
+    }
+
+    /// Initializes this list to be empty, in place.
+    ///
+    /// # Safety
+    /// This must not be called on a list that has nodes, as they will be leaked. Since the list is self-referential,
+    /// it must also not be moved after being initialized.
+    pub unsafe fn init(&mut self) {
+        let sentinel = self.sentinel();
+        self.size = 0;
+        self.next = sentinel;
+        self.end = sentinel;
+    }
+
+    pub fn get_node(&self, idx: usize) -> Option<&ListNode> {
+        if idx >= self.size {
+            None
+        } else {
+            let mut node = self.next;
+            for _ in 0..idx {
+                node = unsafe { (*node).next };
+            }
+            unsafe { Some(&*node) }
+        }
+    }
+
+    /// Walks the raw node pointers to the node at the provided index, without going through a shared reference
+    fn get_node_ptr(&mut self, idx: usize) -> Option<*mut ListNode> {
+        if idx >= self.size {
+            None
+        } else {
+            let mut node = self.next;
+            for _ in 0..idx {
+                node = unsafe { (*node).next };
+            }
+            Some(node)
+        }
+    }
+
+    pub fn node_iter(&self) -> NodeIter<'_> {
+        NodeIter { list: self, count: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Gets the `next` field of the node. The sentinel's field is taken from the list itself, since dereferencing the
+    /// list's pointer to itself would alias the `&mut self` that the list is being modified through.
+    unsafe fn next_field(&mut self, node: *mut ListNode) -> &mut *mut ListNode {
+        if node == self.sentinel() {
+            &mut self.next
+        } else {
+            &mut (*node).next
+        }
+    }
+
+    /// Gets the `prev` field of the node, see [`ResList::next_field`]
+    unsafe fn prev_field(&mut self, node: *mut ListNode) -> &mut *mut ListNode {
+        if node == self.sentinel() {
+            &mut self.end
+        } else {
+            &mut (*node).prev
+        }
+    }
+
+    /// Allocates a new node with the game's allocator and links it in directly before `at`
+    unsafe fn link_before(&mut self, at: *mut ListNode, value: LoadInfo) {
+        let node = alloc_node();
+        let prev = *self.prev_field(at);
+        node.write(ListNode {
+            next: at,
+            prev,
+            data: value,
+        });
+        *self.next_field(prev) = node;
+        *self.prev_field(at) = node;
+        self.size += 1;
+    }
+
+    /// Unlinks the node from the list and frees it, returning the data it held
+    unsafe fn unlink(&mut self, node: *mut ListNode) -> LoadInfo {
+        let ListNode { next, prev, data } = node.read();
+        *self.next_field(prev) = next;
+        *self.prev_field(next) = prev;
+        free_node(node);
+        self.size -= 1;
+        data
+    }
+
+    /// Inserts the value at the front of the list
+    pub fn insert(&mut self, value: LoadInfo) {
+        unsafe {
+            let first = self.next;
+            self.link_before(first, value);
+        }
+    }
+
+    /// Inserts the value at the back of the list
+    pub fn push_back(&mut self, value: LoadInfo) {
+        unsafe {
+            let sentinel = self.sentinel();
+            self.link_before(sentinel, value);
+        }
+    }
+
+    /// Inserts the value at the front of the list, unless an equivalent entry is already present.
+    /// Returns whether the value was inserted
+    pub fn insert_unique(&mut self, value: LoadInfo) -> bool {
+        if self.iter().any(|entry| entry.is_same_load(&value)) {
+            false
+        } else {
+            self.insert(value);
+            true
+        }
+    }
+
+    /// Removes the entry at the provided index, returning it if it exists
+    pub fn remove(&mut self, idx: usize) -> Option<LoadInfo> {
+        let node = self.get_node_ptr(idx)?;
+        unsafe { Some(self.unlink(node)) }
+    }
+
+    /// Removes every entry for which the predicate returns false, returning how many were removed
+    pub fn retain<F: FnMut(&LoadInfo) -> bool>(&mut self, mut f: F) -> usize {
+        let sentinel = self.sentinel();
+        let mut removed = 0;
+        let mut node = self.next;
+        unsafe {
+            while node != sentinel {
+                let next = (*node).next;
+                if !f(&(*node).data) {
+                    self.unlink(node);
+                    removed += 1;
+                }
+                node = next;
+            }
+        }
+        removed
+    }
+
+    /// Removes all of the file entries for the provided file path index, returning how many were removed
+    pub fn remove_file(&mut self, filepath_index: u32) -> usize {
+        self.retain(|entry| !entry.is_file(filepath_index))
+    }
+
+    /// Removes every entry that is a duplicate of an entry earlier in the list, returning how many were removed
+    pub fn dedupe(&mut self) -> usize {
+        let mut seen: Vec<LoadInfo> = vec![];
+        self.retain(|entry| {
+            if seen.iter().any(|other| other.is_same_load(entry)) {
+                false
+            } else {
+                seen.push(*entry);
+                true
+            }
+        })
+    }
+
+    /// Removes every entry from the list
+    pub fn clear(&mut self) {
+        self.retain(|_| false);
+    }
+
+    /// Finds the index of the first entry matching the predicate
+    pub fn position<F: FnMut(&LoadInfo) -> bool>(&self, f: F) -> Option<usize> {
+        self.iter().position(f)
+    }
+
+    /// Finds the index of the file entry for the provided file path index
+    pub fn find_file(&self, filepath_index: u32) -> Option<usize> {
+        self.position(|entry| entry.is_file(filepath_index))
+    }
+
+    pub fn contains_file(&self, filepath_index: u32) -> bool {
+        self.find_file(filepath_index).is_some()
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&LoadInfo> {
+        if idx >= self.size {
+            None
+        } else {
+            let mut node = self.next;
+            for _ in 0..idx {
+                node = unsafe { (*node).next };
+            }
+            unsafe { Some(&(*node).data) }
+        }
+    }
+
+    pub fn get_mut(&mut self, idx: usize) -> Option<&mut LoadInfo> {
+        if idx >= self.size {
+            None
+        } else {
+            let mut node = self.next;
+            for _ in 0..idx {
+                node = unsafe { (*node).next };
+            }
+            unsafe { Some(&mut (*node).data) }
+        }
+    }
+
+    pub fn iter(&self) -> ResListIter<'_> {
+        self.into_iter()
+    }
+
+    pub fn iter_mut(&mut self) -> ResListIterMut<'_> {
+        self.into_iter()
+    }
+}
+
+pub struct NodeIter<'a> {
+    list: &'a ResList,
+    count: usize,
+}
+
+pub struct ResListIter<'a> {
+    list: &'a ResList,
+    count: usize,
+}
+
+pub struct ResListIterMut<'a> {
+    list: &'a mut ResList,
+    count: usize,
+}
+
+impl<'a> Iterator for NodeIter<'a> {
+    type Item = &'a ListNode;
+
+    fn next(&mut self) -> Option<&'a ListNode> {
+        self.count += 1;
+        self.list.get_node(self.count - 1)
+    }
+}
+
+impl<'a> Iterator for ResListIter<'a> {
+    type Item = &'a LoadInfo;
+
+    fn next(&mut self) -> Option<&'a LoadInfo> {
+        self.count += 1;
+        self.list.get(self.count - 1)
+    }
+}
+
+impl<'a> Iterator for ResListIterMut<'a> {
+    type Item = &'a mut LoadInfo;
+
+    fn next(&mut self) -> Option<&'a mut LoadInfo> {
+        unsafe {
+            self.count += 1;
+            std::mem::transmute(self.list.get_mut(self.count - 1))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::Cell, rc::Rc};
+
+    /// Counts how many times it has been dropped, to check that elements are dropped exactly once
+    #[derive(Clone)]
+    struct DropCounter {
+        value: u32,
+        drops: Rc<Cell<usize>>,
+    }
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.drops.set(self.drops.get() + 1);
+        }
+    }
+
+    fn counters(drops: &Rc<Cell<usize>>, count: u32) -> CppVector<DropCounter> {
+        let mut vec = CppVector::new();
+        for value in 0..count {
+            vec.push(DropCounter {
+                value,
+                drops: drops.clone(),
+            });
+        }
+        vec
+    }
+
+    fn values(vec: &CppVector<DropCounter>) -> Vec<u32> {
+        vec.iter().map(|counter| counter.value).collect()
+    }
+
+    #[test]
+    fn vector_reserve_and_realloc() {
+        let mut vec = CppVector::<u32>::new();
+        assert_eq!(vec.len(), 0);
+        assert_eq!(vec.capacity(), 0);
+        assert!(vec.as_slice().is_empty());
+
+        vec.reserve(3);
+        assert!(vec.capacity() >= 3);
+        assert_eq!(vec.len(), 0);
+
+        for value in 0..100 {
+            vec.push(value);
+        }
+        assert_eq!(vec.len(), 100);
+        assert!(vec.capacity() >= 100);
+        assert!(vec.iter().copied().eq(0..100));
+
+        let capacity = vec.capacity();
+        vec.reserve(capacity - vec.len());
+        assert_eq!(vec.capacity(), capacity);
+
+        vec.extend_from_slice(&[100, 101]);
+        assert_eq!(vec[101], 101);
+        assert_eq!(vec.pop(), Some(101));
+        assert_eq!(vec.len(), 101);
+    }
+
+    #[test]
+    fn vector_with_capacity() {
+        let vec = CppVector::<u64>::with_capacity(10);
+        assert!(vec.capacity() >= 10);
+        assert!(vec.is_empty());
+
+        let vec = CppVector::<u64>::with_capacity(0);
+        assert_eq!(vec.capacity(), 0);
+    }
+
+    #[test]
+    fn vector_insert() {
+        let mut vec = CppVector::from_slice(&[1, 3]);
+        vec.insert(0, 0);
+        vec.insert(2, 2);
+        vec.insert(4, 4);
+        assert_eq!(vec.as_slice(), [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn vector_insert_out_of_bounds() {
+        let mut vec = CppVector::from_slice(&[1]);
+        vec.insert(2, 0);
+    }
+
+    #[test]
+    fn vector_remove() {
+        let drops = Rc::new(Cell::new(0));
+        let mut vec = counters(&drops, 4);
+
+        assert_eq!(vec.remove(1).value, 1);
+        assert_eq!(drops.get(), 1);
+        assert_eq!(vec.remove(2).value, 3);
+        assert_eq!(values(&vec), [0, 2]);
+
+        drop(vec);
+        assert_eq!(drops.get(), 4);
+    }
+
+    #[test]
+    #[should_panic]
+    fn vector_remove_out_of_bounds() {
+        let mut vec = CppVector::from_slice(&[1]);
+        vec.remove(1);
+    }
+
+    #[test]
+    fn vector_retain() {
+        let drops = Rc::new(Cell::new(0));
+        let mut vec = counters(&drops, 6);
+
+        vec.retain(|counter| counter.value % 2 == 0);
+        assert_eq!(values(&vec), [0, 2, 4]);
+        assert_eq!(drops.get(), 3);
+
+        vec.retain(|_| true);
+        assert_eq!(values(&vec), [0, 2, 4]);
+
+        vec.retain(|_| false);
+        assert!(vec.is_empty());
+        assert_eq!(drops.get(), 6);
+    }
+
+    #[test]
+    fn vector_truncate() {
+        let drops = Rc::new(Cell::new(0));
+        let mut vec = counters(&drops, 5);
+
+        vec.truncate(10);
+        assert_eq!(vec.len(), 5);
+        assert_eq!(drops.get(), 0);
+
+        vec.truncate(2);
+        assert_eq!(values(&vec), [0, 1]);
+        assert_eq!(drops.get(), 3);
+
+        vec.clear();
+        assert!(vec.is_empty());
+        assert_eq!(drops.get(), 5);
+    }
+
+    #[test]
+    fn vector_drop() {
+        let drops = Rc::new(Cell::new(0));
+        drop(counters(&drops, 8));
+        assert_eq!(drops.get(), 8);
+
+        drop(CppVector::<DropCounter>::new());
+    }
+
+    #[test]
+    fn vector_clone() {
+        let drops = Rc::new(Cell::new(0));
+        let vec = counters(&drops, 3);
+        let mut clone = vec.clone();
+
+        clone.push(DropCounter {
+            value: 3,
+            drops: drops.clone(),
+        });
+        assert_eq!(values(&vec), [0, 1, 2]);
+        assert_eq!(values(&clone), [0, 1, 2, 3]);
+        assert_ne!(vec.as_ptr(), clone.as_ptr());
+
+        drop(vec);
+        assert_eq!(drops.get(), 3);
+        drop(clone);
+        assert_eq!(drops.get(), 7);
+    }
+
+    fn file(filepath_index: u32) -> LoadInfo {
+        LoadInfo {
+            ty: LoadType::File,
+            filepath_index,
+            directory_index: 0,
+            files_to_load: 0,
+        }
+    }
+
+    fn directory(directory_index: u32) -> LoadInfo {
+        LoadInfo {
+            ty: LoadType::Directory,
+            filepath_index: 0,
+            directory_index,
+            files_to_load: 0,
+        }
+    }
+
+    /// Creates an empty list in Rust owned memory, boxed so that it doesn't move after being initialized
+    fn new_list() -> Box<ResList> {
+        let mut list = Box::new(ResList {
+            size: 0,
+            next: std::ptr::null_mut(),
+            end: std::ptr::null_mut(),
+        });
+        unsafe {
+            list.init();
+        }
+        list
+    }
+
+    fn file_indices(list: &ResList) -> Vec<u32> {
+        list.iter().map(|entry| entry.filepath_index).collect()
+    }
+
+    #[test]
+    fn empty_list() {
+        let mut list = new_list();
+        assert!(list.is_empty());
+        assert_eq!(list.iter().count(), 0);
+        assert!(list.get(0).is_none());
+        assert!(list.remove(0).is_none());
+        assert_eq!(list.retain(|_| false), 0);
+    }
+
+    #[test]
+    fn push_and_insert() {
+        let mut list = new_list();
+        list.push_back(file(1));
+        list.push_back(file(2));
+        list.insert(file(0));
+
+        assert_eq!(list.len(), 3);
+        assert_eq!(file_indices(&list), [0, 1, 2]);
+        assert_eq!(list.node_iter().count(), 3);
+        list.clear();
+    }
+
+    #[test]
+    fn links_stay_consistent() {
+        let mut list = new_list();
+        for index in 0..4 {
+            list.push_back(file(index));
+        }
+        list.remove(1);
+
+        let sentinel = list.sentinel();
+        let mut node = list.next;
+        let mut prev = sentinel;
+        let mut count = 0;
+        unsafe {
+            while node != sentinel {
+                assert_eq!((*node).prev, prev);
+                prev = node;
+                node = (*node).next;
+                count += 1;
+            }
+        }
+        assert_eq!(list.end, prev);
+        assert_eq!(count, list.len());
+        list.clear();
+    }
+
+    #[test]
+    fn remove_by_index() {
+        let mut list = new_list();
+        for index in 0..4 {
+            list.push_back(file(index));
+        }
+
+        assert_eq!(list.remove(0).map(|entry| entry.filepath_index), Some(0));
+        assert_eq!(list.remove(2).map(|entry| entry.filepath_index), Some(3));
+        assert!(list.remove(2).is_none());
+        assert_eq!(file_indices(&list), [1, 2]);
+
+        list.clear();
+        assert!(list.is_empty());
+        list.push_back(file(5));
+        assert_eq!(file_indices(&list), [5]);
+        list.clear();
+    }
+
+    #[test]
+    fn remove_files_and_dedupe() {
+        let mut list = new_list();
+        list.push_back(file(1));
+        list.push_back(directory(1));
+        list.push_back(file(2));
+        list.push_back(file(1));
+        list.push_back(directory(1));
+
+        assert_eq!(list.dedupe(), 2);
+        assert_eq!(list.len(), 3);
+        assert!(!list.insert_unique(file(2)));
+        assert!(list.insert_unique(file(3)));
+
+        assert_eq!(list.remove_file(1), 1);
+        assert!(!list.contains_file(1));
+        assert_eq!(list.find_file(2), Some(2));
+        list.clear();
+    }
+
+    #[test]
+    fn iter_mut_updates_entries() {
+        let mut list = new_list();
+        list.push_back(file(1));
+        list.push_back(file(2));
+
+        for entry in list.iter_mut() {
+            entry.filepath_index += 10;
+        }
+        assert_eq!(file_indices(&list), [11, 12]);
+        list.clear();
+    }
+}
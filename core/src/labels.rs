@@ -0,0 +1,210 @@
+use std::{collections::HashMap, path::Path};
+
+use log::{error, info};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use smash_arc::Hash40;
+
+const LABEL_FILE_PATH: &str = "sd:/ultimate/stage-alts/labels.csv";
+
+/// Every string that we know the hash of, either from the label file or from hashing it ourselves
+static LABELS: Lazy<RwLock<HashMap<Hash40, String>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// The strings that the plugin hashes with `Hash40::from` when building paths. These are registered so that
+/// paths which are joined from them (such as the UI paths) can be labeled.
+const KNOWN_STRINGS: &[&str] = &[
+    "/",
+    "stage",
+    "common",
+    "normal",
+    "battle",
+    "end",
+    "resultstage",
+    "effect/stage",
+    "ui",
+    "replace",
+    "replace_patch",
+    "ui/replace/stage",
+    "ui/replace_patch/stage",
+    "stage_0",
+    "stage_1",
+    "stage_2",
+    "stage_3",
+    "stage_4",
+    "stage_0_",
+    "stage_1_",
+    "stage_2_",
+    "stage_3_",
+    "stage_4_",
+    ".bntx",
+    "battlefield",
+    "battlefields",
+    "battlefieldl",
+    "wifi-safe.flag",
+    "wifi-ignore.flag",
+];
+
+/// A wrapper around a hash which displays the string it was made from when it is known, otherwise the raw hash
+#[derive(Copy, Clone)]
+pub struct Label(pub Hash40);
+
+impl std::fmt::Display for Label {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match LABELS.read().get(&self.0) {
+            Some(label) => write!(f, "{}", label),
+            None => write!(f, "{:#x}", self.0 .0),
+        }
+    }
+}
+
+/// Gets the string for the hash, if it is known
+pub fn get(hash: Hash40) -> Option<String> {
+    LABELS.read().get(&hash).cloned()
+}
+
+/// Registers the string for its hash
+pub fn register(string: &str) {
+    register_hash(Hash40::from(string), string);
+}
+
+/// Registers the string for an already computed hash
+pub fn register_hash(hash: Hash40, string: &str) {
+    LABELS
+        .write()
+        .entry(hash)
+        .or_insert_with(|| string.to_string());
+}
+
+/// Hashes the string and registers it, returning the hash
+pub fn hash(string: &str) -> Hash40 {
+    let hash = Hash40::from(string);
+    register_hash(hash, string);
+    hash
+}
+
+/// Registers the label of two hashes that have been concatenated, if both of them are known and the result isn't
+pub fn register_concat(first: Hash40, second: Hash40, result: Hash40) {
+    let labels = LABELS.read();
+    if labels.contains_key(&result) {
+        return;
+    }
+
+    let (first, second) = match (labels.get(&first), labels.get(&second)) {
+        (Some(first), Some(second)) => (first, second),
+        _ => return,
+    };
+
+    let label = format!("{}{}", first, second);
+    drop(labels);
+
+    register_hash(result, &label);
+}
+
+/// Parses a line of the label file into the hash and its label. Returns `None` for blank lines and for lines with an
+/// invalid hash.
+fn parse_line(line: &str) -> Option<(Hash40, &str)> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+
+    match line.split_once(',') {
+        Some((hash, label)) => match u64::from_str_radix(hash.trim_start_matches("0x"), 16) {
+            Ok(hash) => Some((Hash40(hash), label)),
+            Err(_) => {
+                error!("Invalid hash in label file: {}", line);
+                None
+            }
+        },
+        None => Some((Hash40::from(line), line)),
+    }
+}
+
+/// Loads the label file (if there is one) and registers all of our known strings.
+///
+/// The label file uses the same format as ParamLabels, with each line being `0x<hash>,<label>`. Lines without a hash are
+/// treated as a string to be hashed.
+pub fn init() {
+    for string in KNOWN_STRINGS {
+        register(string);
+    }
+
+    if !Path::new(LABEL_FILE_PATH).exists() {
+        return;
+    }
+
+    let data = match std::fs::read_to_string(LABEL_FILE_PATH) {
+        Ok(data) => data,
+        Err(e) => {
+            error!("Failed to read the label file {}: {}", LABEL_FILE_PATH, e);
+            return;
+        }
+    };
+
+    let mut labels = LABELS.write();
+    let mut count = 0;
+    for (hash, label) in data.lines().filter_map(parse_line) {
+        labels.insert(hash, label.to_string());
+        count += 1;
+    }
+
+    info!("Loaded {} labels from {}", count, LABEL_FILE_PATH);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_lines() {
+        let cases: &[(&str, Option<(Hash40, &str)>)] = &[
+            ("0x1234,stage", Some((Hash40(0x1234), "stage"))),
+            ("1234,stage", Some((Hash40(0x1234), "stage"))),
+            ("  0x0a1b2c3d4e,ui/replace  ", Some((Hash40(0x0a1b2c3d4e), "ui/replace"))),
+            // only the first comma separates the hash from the label
+            ("0x10,a,b", Some((Hash40(0x10), "a,b"))),
+            // lines without a hash are hashed
+            ("battlefield", Some((Hash40::from("battlefield"), "battlefield"))),
+            ("", None),
+            ("   ", None),
+            ("0xzz,stage", None),
+            (",stage", None),
+        ];
+
+        for (line, expected) in cases {
+            assert_eq!(parse_line(line), *expected, "line {:?}", line);
+        }
+    }
+
+    #[test]
+    fn register_concat_cases() {
+        // every case uses its own strings, since the labels are shared with the other tests
+        // (first, second, label already registered for the result, expected label of the result)
+        type Case = (&'static str, &'static str, Option<&'static str>, Option<&'static str>);
+        let cases: &[Case] = &[
+            ("concat_a/", "known", None, Some("concat_a/known")),
+            ("concat_b", "_suffix.bntx", None, Some("concat_b_suffix.bntx")),
+            // the result keeps its existing label
+            ("concat_c/", "known", Some("existing"), Some("existing")),
+        ];
+
+        for (first, second, existing, expected) in cases {
+            let result = Hash40::from(format!("{}{}", first, second).as_str());
+            if let Some(existing) = existing {
+                register_hash(result, existing);
+            }
+
+            register_concat(hash(first), hash(second), result);
+            assert_eq!(get(result).as_deref(), *expected, "{:?} + {:?}", first, second);
+        }
+
+        // a part without a label can't be labeled, so neither can the result
+        let result = Hash40::from("concat_d/unknown");
+        register_concat(hash("concat_d/"), Hash40::from("unknown"), result);
+        assert_eq!(get(result), None);
+
+        let result = Hash40::from("unknown_firstconcat_e");
+        register_concat(Hash40::from("unknown_first"), hash("concat_e"), result);
+        assert_eq!(get(result), None);
+    }
+}
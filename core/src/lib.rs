@@ -0,0 +1,4 @@
+pub mod containers;
+pub mod labels;
+pub mod pattern;
+pub mod selection;
@@ -0,0 +1,141 @@
+/// Parses a byte pattern such as `"E0 03 ?? AA"` into a list of bytes, where `None` matches any byte
+pub fn parse_pattern(pattern: &str) -> Option<Vec<Option<u8>>> {
+    pattern
+        .split_whitespace()
+        .map(|byte| {
+            if byte == "??" || byte == "?" {
+                Some(None)
+            } else {
+                u8::from_str_radix(byte, 16).ok().map(Some)
+            }
+        })
+        .collect()
+}
+
+/// Finds the first position in the haystack that matches the pattern
+pub fn find_pattern(haystack: &[u8], pattern: &[Option<u8>]) -> Option<usize> {
+    if pattern.is_empty() || pattern.len() > haystack.len() {
+        return None;
+    }
+
+    haystack.windows(pattern.len()).position(|window| {
+        window
+            .iter()
+            .zip(pattern.iter())
+            .all(|(byte, expected)| match expected {
+                Some(expected) => byte == expected,
+                None => true,
+            })
+    })
+}
+
+/// Decodes the address loaded by an `adrp` instruction followed by either an `add` (immediate) or an
+/// `ldr` (64-bit, unsigned immediate), given the offset of the `adrp` instruction
+pub fn resolve_adrp(pc: usize, adrp: u32, next: u32) -> Option<usize> {
+    // adrp: 1 immlo(2) 10000 immhi(19) Rd(5)
+    if adrp & 0x9F00_0000 != 0x9000_0000 {
+        return None;
+    }
+
+    let immlo = ((adrp >> 29) & 0b11) as i64;
+    let immhi = ((adrp >> 5) & 0x7_FFFF) as i64;
+    // sign extend the 21-bit immediate
+    let imm = (((immhi << 2) | immlo) << 43) >> 43;
+    let page = ((pc & !0xFFF) as i64 + (imm << 12)) as usize;
+
+    let imm12 = ((next >> 10) & 0xFFF) as usize;
+    if next & 0xFF80_0000 == 0x9100_0000 {
+        // add (immediate, 64-bit), optionally shifted by 12
+        let shift = if next & (1 << 22) != 0 { 12 } else { 0 };
+        Some(page + (imm12 << shift))
+    } else if next & 0xFFC0_0000 == 0xF940_0000 {
+        // ldr (immediate, unsigned offset, 64-bit)
+        Some(page + imm12 * 8)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes `adrp x0, <pages>`, where the page count is relative to the page of the instruction
+    fn adrp(pages: i32) -> u32 {
+        let imm = (pages as u32) & 0x1F_FFFF;
+        0x9000_0000 | ((imm & 0b11) << 29) | ((imm >> 2) << 5)
+    }
+
+    /// Encodes `add x0, x0, #<imm12>`, optionally shifted by 12
+    fn add(imm12: u32, shifted: bool) -> u32 {
+        0x9100_0000 | ((shifted as u32) << 22) | (imm12 << 10)
+    }
+
+    /// Encodes `ldr x0, [x0, #<imm12 * 8>]`
+    fn ldr(imm12: u32) -> u32 {
+        0xF940_0000 | (imm12 << 10)
+    }
+
+    #[test]
+    fn parse_patterns() {
+        let cases: &[(&str, Option<Vec<Option<u8>>>)] = &[
+            ("E0 03 ?? AA", Some(vec![Some(0xE0), Some(0x03), None, Some(0xAA)])),
+            ("? ff", Some(vec![None, Some(0xFF)])),
+            ("  01   02 ", Some(vec![Some(0x01), Some(0x02)])),
+            ("", Some(vec![])),
+            ("ZZ", None),
+            ("01 100", None),
+        ];
+
+        for (pattern, expected) in cases {
+            assert_eq!(&parse_pattern(pattern), expected, "pattern {:?}", pattern);
+        }
+    }
+
+    #[test]
+    fn find_patterns() {
+        let haystack = [0x00, 0x11, 0x22, 0x33, 0x11, 0x22, 0x44];
+        let cases: &[(&str, Option<usize>)] = &[
+            ("11 22", Some(1)),
+            ("11 ?? 44", Some(4)),
+            ("?? 33", Some(2)),
+            ("22 44", Some(5)),
+            ("00", Some(0)),
+            ("11 22 55", None),
+            ("44 00", None),
+            ("00 11 22 33 11 22 44 55", None),
+            ("", None),
+        ];
+
+        for (pattern, expected) in cases {
+            let pattern = parse_pattern(pattern).unwrap();
+            assert_eq!(find_pattern(&haystack, &pattern), *expected, "pattern {:?}", pattern);
+        }
+    }
+
+    #[test]
+    fn resolve_adrp_pairs() {
+        let cases: &[(usize, u32, u32, Option<usize>)] = &[
+            (0x1234, adrp(2), add(0x10, false), Some(0x3010)),
+            (0x1234, adrp(0), add(0x10, true), Some(0x11000)),
+            (0x5000, adrp(-3), ldr(2), Some(0x2010)),
+            (0x10_0FFC, adrp(-0x100), add(0xFFF, false), Some(0xFFF)),
+            (0x1000, adrp(0xF_FFFF), add(0, false), Some(0x1000 + 0xF_FFFF * 0x1000)),
+            // adr instead of adrp
+            (0x1000, adrp(1) & !0x8000_0000, add(0, false), None),
+            // neither an add nor an ldr
+            (0x1000, adrp(1), 0xD503_201F, None),
+        ];
+
+        for (pc, adrp, next, expected) in cases {
+            assert_eq!(
+                resolve_adrp(*pc, *adrp, *next),
+                *expected,
+                "pc {:#x}, adrp {:#x}, next {:#x}",
+                pc,
+                adrp,
+                next
+            );
+        }
+    }
+}
@@ -0,0 +1,298 @@
+use log::{error, info};
+use smash_arc::Hash40;
+
+use crate::labels::Label;
+
+/// The form of a stage that alts are picked for.
+///
+/// The Ω form only swaps out the stage's platform and is loaded from the same `battle` folder as the battlefield form,
+/// so alts only have flags for the normal and battle folders. Form-aware picking (wifi-safe and ignore flags, and random
+/// picks) therefore only covers the normal and battlefield forms, and the Ω form is treated as the battlefield form.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StageForm {
+    Normal,
+    Battle,
+}
+
+impl StageForm {
+    /// Gets the form for the `stage_form` of the stage select screen, where the Ω form (2) is treated as battle
+    pub fn from_index(index: i32) -> Option<Self> {
+        match index {
+            0 => Some(Self::Normal),
+            1 | 2 => Some(Self::Battle),
+            _ => None,
+        }
+    }
+
+    /// Gets the form requested by one of the random panels, or `None` for the panel that doesn't pick a form
+    pub fn from_random_panel(ui_hash: Hash40) -> Option<Self> {
+        if ui_hash == Hash40::from("ui_stage_random_normal") {
+            Some(Self::Normal)
+        } else if ui_hash == Hash40::from("ui_stage_random_battle") || ui_hash == Hash40::from("ui_stage_random_end") {
+            Some(Self::Battle)
+        } else {
+            None
+        }
+    }
+
+    /// Checks if the form is loaded from the `battle` folder of the stage
+    pub fn is_battle(self) -> bool {
+        self == Self::Battle
+    }
+}
+
+impl std::fmt::Display for StageForm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Normal => write!(f, "normal"),
+            Self::Battle => write!(f, "battle"),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Selection {
+    Regular { name: Hash40, alt: usize },
+
+    /// A random stage, with the form requested by the random panel if it picks one
+    Random { form: Option<StageForm> },
+    Invalid,
+}
+
+impl std::fmt::Display for Selection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Regular { name, alt } => {
+                write!(f, "{} @ Alt #{}", Label(*name), alt)
+            }
+            Self::Random { form: None } => {
+                write!(f, "Random")
+            }
+            Self::Random { form: Some(form) } => {
+                write!(f, "Random ({})", form)
+            }
+            Self::Invalid => {
+                write!(f, "Invalid")
+            }
+        }
+    }
+}
+
+/// The direction that the alts of a stage are cycled through in
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CycleDirection {
+    Forward,
+    Backward,
+}
+
+/// Cycles through the alt order in the provided direction, returning the first alt after `current` that passes the
+/// check. The cycle wraps around, so `current` itself is the last alt that is checked.
+///
+/// If `current` is not in the order, the cycle starts at the first alt of the order instead (which is checked first).
+/// Returns `None` if the order is empty or none of its alts pass the check.
+pub fn cycle_alt(
+    order: &[usize],
+    current: usize,
+    direction: CycleDirection,
+    mut is_usable: impl FnMut(usize) -> bool,
+) -> Option<usize> {
+    let len = order.len();
+    if len == 0 {
+        return None;
+    }
+
+    let (start, steps) = match order.iter().position(|alt| *alt == current) {
+        Some(position) => (position, 1..=len),
+        None => (0, 0..=len - 1),
+    };
+
+    steps
+        .map(|step| match direction {
+            CycleDirection::Forward => order[(start + step) % len],
+            CycleDirection::Backward => order[(start + len - step) % len],
+        })
+        .find(|alt| is_usable(*alt))
+}
+
+/// Picks one of the entries with a random roll, with each entry being picked in proportion to its weight.
+/// Returns `None` if there are no entries or all of them have a weight of 0.
+pub fn pick_weighted<T: Copy>(entries: &[T], weight: impl Fn(T) -> u64, roll: u64) -> Option<T> {
+    let total: u64 = entries.iter().map(|entry| weight(*entry)).sum();
+    if total == 0 {
+        return None;
+    }
+
+    let mut roll = roll % total;
+    entries.iter().copied().find(|entry| {
+        let weight = weight(*entry);
+        if roll < weight {
+            true
+        } else {
+            roll -= weight;
+            false
+        }
+    })
+}
+
+/// A best-of series, where each game of the set can have a predetermined selection
+pub struct Series {
+    /// The selection that was scheduled for each game of the series. Games without one use the selection from the
+    /// stage select screen (the counterpick), which is kept separately so that it never replaces a scheduled game.
+    pub games: Vec<Option<Selection>>,
+
+    /// The number of games that have been started, which is also the index of the next game
+    pub played: usize,
+}
+
+impl Series {
+    pub fn new(game_count: usize) -> Self {
+        Self {
+            games: vec![None; game_count],
+            played: 0,
+        }
+    }
+
+    /// Gets the index of the next game, or `None` if every game of the series has been started
+    pub fn next_game(&self) -> Option<usize> {
+        (self.played < self.games.len()).then_some(self.played)
+    }
+
+    /// Starts the next game of the series, returning its scheduled selection if it has one for the incoming stage,
+    /// otherwise the counterpick from the stage select screen
+    pub fn start_game(&mut self, incoming: Hash40, counterpick: Selection) -> Selection {
+        let game = match self.next_game() {
+            Some(game) => game,
+            None => {
+                info!("Every game of the series has been played, using the stage select screen's selection");
+                return counterpick;
+            }
+        };
+
+        self.played += 1;
+        info!("Starting game {} of the series", game);
+
+        match self.games[game] {
+            Some(Selection::Regular { name, alt }) if name == incoming => Selection::Regular { name, alt },
+            Some(Selection::Regular { name, .. }) => {
+                error!(
+                    "Game {} of the series is on {}, but {} is loading. Using the stage select screen's selection",
+                    game, Label(name), Label(incoming)
+                );
+                counterpick
+            }
+            Some(scheduled) => scheduled,
+            None => counterpick,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cycle_alt_cases() {
+        use CycleDirection::{Backward, Forward};
+
+        // (order, current alt, direction, unusable alts, expected alt)
+        type Case = (&'static [usize], usize, CycleDirection, &'static [usize], Option<usize>);
+        let cases: &[Case] = &[
+            (&[0, 1, 2], 0, Forward, &[], Some(1)),
+            (&[0, 1, 2], 2, Backward, &[], Some(1)),
+            // wrapping around
+            (&[0, 1, 2], 2, Forward, &[], Some(0)),
+            (&[0, 1, 2], 0, Backward, &[], Some(2)),
+            // a custom order
+            (&[3, 1, 2], 1, Forward, &[], Some(2)),
+            (&[3, 1, 2], 1, Backward, &[], Some(3)),
+            // skipping unusable alts
+            (&[0, 1, 2, 3], 0, Forward, &[1, 2], Some(3)),
+            (&[0, 1, 2, 3], 0, Backward, &[3], Some(2)),
+            // the current alt is the only usable one
+            (&[0, 1, 2], 1, Forward, &[0, 2], Some(1)),
+            // the current alt is missing from the order, so the cycle starts at the front of the order
+            (&[0, 1, 2], 5, Forward, &[], Some(0)),
+            (&[0, 1, 2], 5, Backward, &[], Some(0)),
+            (&[0, 1, 2], 5, Forward, &[0], Some(1)),
+            (&[0, 1, 2], 5, Backward, &[0], Some(2)),
+            // every alt is unusable
+            (&[0, 1, 2], 0, Forward, &[0, 1, 2], None),
+            (&[0, 1, 2], 5, Backward, &[0, 1, 2], None),
+            // an empty order
+            (&[], 0, Forward, &[], None),
+            (&[], 0, Backward, &[], None),
+        ];
+
+        for (order, current, direction, unusable, expected) in cases {
+            assert_eq!(
+                cycle_alt(order, *current, *direction, |alt| !unusable.contains(&alt)),
+                *expected,
+                "order {:?}, current {}, {:?}, unusable {:?}",
+                order,
+                current,
+                direction,
+                unusable
+            );
+        }
+    }
+
+    #[test]
+    fn scheduled_series_games_survive_counterpicks() {
+        let stage = Hash40(0x1234);
+        let other_stage = Hash40(0x5678);
+        let scheduled = Selection::Regular { name: stage, alt: 2 };
+
+        let mut series = Series::new(3);
+        series.games[0] = Some(scheduled);
+        series.games[2] = Some(scheduled);
+
+        // the stage select screen confirmed a different alt of the same stage, but the scheduled alt is used
+        let counterpick = Selection::Regular { name: stage, alt: 5 };
+        assert_eq!(series.start_game(stage, counterpick), scheduled);
+        assert_eq!(series.games[0], Some(scheduled));
+
+        // the second game wasn't scheduled, so the counterpick is used without being written into the schedule
+        assert_eq!(series.start_game(stage, counterpick), counterpick);
+        assert_eq!(series.games[1], None);
+
+        // a scheduled game on another stage falls back to the counterpick
+        let counterpick = Selection::Regular { name: other_stage, alt: 1 };
+        assert_eq!(series.start_game(other_stage, counterpick), counterpick);
+        assert_eq!(series.games[2], Some(scheduled));
+
+        // once every game has been played, the counterpick is always used
+        assert_eq!(series.next_game(), None);
+        assert_eq!(series.start_game(stage, counterpick), counterpick);
+        assert_eq!(series.played, 3);
+    }
+
+    #[test]
+    fn pick_weighted_cases() {
+        // (entries, weights, roll, expected entry)
+        type Case = (&'static [usize], &'static [u64], u64, Option<usize>);
+        let cases: &[Case] = &[
+            (&[], &[], 0, None),
+            (&[0, 1, 2], &[0, 0, 0], 0, None),
+            (&[0, 1, 2], &[0, 5, 0], 0, Some(1)),
+            (&[0, 1, 2], &[0, 5, 0], 4, Some(1)),
+            (&[0, 1, 2], &[1, 0, 3], 0, Some(0)),
+            (&[0, 1, 2], &[1, 0, 3], 1, Some(2)),
+            (&[0, 1, 2], &[1, 0, 3], 3, Some(2)),
+            (&[0, 1, 2], &[1, 1, 1], 1, Some(1)),
+            // the roll wraps around the total weight
+            (&[0, 1, 2], &[1, 1, 1], 5, Some(2)),
+            (&[0, 1, 2], &[1, 0, 3], u64::MAX, Some(2)),
+        ];
+
+        for (entries, weights, roll, expected) in cases {
+            assert_eq!(
+                pick_weighted(entries, |entry| weights[entry], *roll),
+                *expected,
+                "entries {:?}, weights {:?}, roll {}",
+                entries,
+                weights,
+                roll
+            );
+        }
+    }
+}
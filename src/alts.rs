@@ -1,5 +1,5 @@
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     ops::{Deref, DerefMut},
     sync::Arc,
 };
@@ -9,32 +9,19 @@ use once_cell::sync::Lazy;
 use parking_lot::RwLock;
 use smash_arc::{ArcLookup, FilePath, FilePathIdx, Hash40, HashToIndex, SearchLookup};
 
-use crate::search::{FlattenVec, SearchEntry, SearchEx};
-
-use crate::{search::walk_search_section, types::FilesystemInfo, Hash40Ext};
+pub use stage_alts_core::selection::{cycle_alt, pick_weighted, CycleDirection, Selection, Series, StageForm};
 
-#[derive(Copy, Clone, PartialEq, Eq)]
-pub enum Selection {
-    Regular { name: Hash40, alt: usize },
-    Random,
-    Invalid,
-}
+use crate::search::{FlattenVec, SearchEntry, SearchEx};
 
-impl std::fmt::Display for Selection {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Regular { name, alt } => {
-                write!(f, "{:#x} @ Alt #{}", name.0, alt)
-            }
-            Self::Random => {
-                write!(f, "Random")
-            }
-            Self::Invalid => {
-                write!(f, "Invalid")
-            }
-        }
-    }
-}
+use crate::{
+    config::{self, ModePolicy, RandomPolicy, ResultStagePolicy, RuleAlt, RuleConfig},
+    labels,
+    modes::GameMode,
+    random_toggles,
+    search::walk_search_section,
+    types::FilesystemInfo,
+    Hash40Ext,
+};
 
 /// The main structure to represent information for a specific stage alt
 pub struct StageAlt {
@@ -46,6 +33,14 @@ pub struct StageAlt {
     /// This is intended to be used in the replacement of the file list to load.
     pub alt_folders: HashMap<Hash40, Hash40>,
 
+    /// The vanilla folders of `alt_folders` that are inside of the stage's `normal` folder, which are only loaded for
+    /// the normal form
+    pub normal_folders: HashSet<Hash40>,
+
+    /// The vanilla folders of `alt_folders` that are inside of the stage's `battle` folder, which are only loaded for
+    /// the battlefield form
+    pub battle_folders: HashSet<Hash40>,
+
     /// The mapping of non-shared files to their base file link index
     /// This is used to unshare and reshare files at runtime to prevent weird, inconsistent issues (such as that with
     /// Final Heaven)
@@ -58,6 +53,74 @@ pub struct StageAlt {
     pub is_normal_ignore: bool,
     pub is_battle_ws: bool,
     pub is_battle_ignore: bool,
+
+    /// The tags of the alt, from the `tag-<name>.flag` files in either of its form folders
+    pub tags: HashSet<String>,
+}
+
+impl StageAlt {
+    /// Checks if the alt has a `wifi-safe.flag` in the folder that the form is loaded from
+    pub fn is_wifi_safe(&self, form: StageForm) -> bool {
+        if form.is_battle() {
+            self.is_battle_ws
+        } else {
+            self.is_normal_ws
+        }
+    }
+
+    /// Checks if the alt has a `wifi-ignore.flag` in the folder that the form is loaded from
+    pub fn is_ignored(&self, form: StageForm) -> bool {
+        if form.is_battle() {
+            self.is_battle_ignore
+        } else {
+            self.is_normal_ignore
+        }
+    }
+
+    /// Gets the folders of the alt that are loaded for the form, as pairs of the vanilla folder and the alt's folder.
+    /// Folders outside of the stage's form folders (such as the effect folder) are loaded for every form.
+    pub fn get_folders_for_form(&self, form: StageForm) -> impl Iterator<Item = (Hash40, Hash40)> + '_ {
+        let other_form = if form.is_battle() {
+            &self.normal_folders
+        } else {
+            &self.battle_folders
+        };
+
+        self.alt_folders
+            .iter()
+            .filter(move |(base, _)| !other_form.contains(base))
+            .map(|(base, modded)| (*base, *modded))
+    }
+}
+
+/// The files of an alt that have been preloaded for one of the stage previews
+pub struct Preload {
+    pub stage_name: Hash40,
+
+    /// The preloaded files of each folder, keyed by the vanilla folder. A folder is released once its directory has
+    /// been swapped, since the directory holds its own references to the files from then on.
+    pub folders: HashMap<Hash40, Vec<u32>>,
+}
+
+/// A change to the preloads that was requested from the stage select screen.
+///
+/// The stage select screen runs on the UI thread, but the references to the preloaded files have to be taken (and
+/// the files added to the load lists) while the resource thread isn't using them. The requests are queued and then
+/// handled at the start of the resource loop.
+pub enum PreloadRequest {
+    /// Preload the files of the alt for the form, replacing the preload of the stage preview
+    Queue {
+        preview_index: usize,
+        stage_name: Hash40,
+        alt: usize,
+        form: StageForm,
+    },
+
+    /// Cancel the preload of the stage preview
+    Cancel(usize),
+
+    /// Release every preload
+    ReleaseAll,
 }
 
 pub struct StageAltInfo {
@@ -68,16 +131,206 @@ pub struct StageAltInfo {
     pub alts_found: Vec<Arc<StageAlt>>,
 }
 
+/// The policy used when injecting the files of a loading directory into the resource service's load lists.
+///
+/// When a directory is queued for loading, some of its child files will not be loaded by the game unless they are
+/// explicitly added to the load lists. This is done in the `res_loop_start` hook, and this policy controls which
+/// files are picked and where they go.
+#[derive(Debug, Copy, Clone)]
+pub struct LoadInjectionPolicy {
+    /// The mask checked against the `unused4` flags of each child file's info, any file with one of these bits set is injected
+    pub flag_mask: u32,
+
+    /// The load list to inject the files into. When this is `None`, the files are injected into the same list as their directory
+    pub target_list: Option<usize>,
+
+    /// Whether to skip files that are already present in the target list
+    pub dedupe: bool,
+
+    /// Whether to only log the files that would be injected without modifying the load lists
+    pub dry_run: bool,
+}
+
+impl Default for LoadInjectionPolicy {
+    fn default() -> Self {
+        Self {
+            flag_mask: 5,
+            target_list: None,
+            dedupe: true,
+            dry_run: false,
+        }
+    }
+}
+
+/// The rules used to pick alts, from either one of the named profiles in the config or the top-level settings
+pub struct SelectionProfile {
+    /// The name of the profile, or `None` for the top-level settings
+    pub name: Option<String>,
+
+    /// How alts are picked when they are chosen randomly
+    pub random: RandomPolicy,
+
+    /// The tags used to filter the alts, see [`StageAltManager::is_alt_visible`]
+    pub tags: HashSet<String>,
+
+    /// The alts that can be used for each stage. Stages that are not present can use any alt.
+    pub allowed_alts: HashMap<Hash40, HashSet<usize>>,
+
+    /// The random weights of the alts for each stage, indexed by alt
+    pub weights: HashMap<Hash40, Vec<u32>>,
+}
+
+impl SelectionProfile {
+    /// Builds the profile from the top-level settings of the config
+    pub fn base() -> Self {
+        let config = config::get();
+        Self {
+            name: None,
+            random: config.random,
+            tags: config.active_tags.iter().cloned().collect(),
+            allowed_alts: HashMap::new(),
+            weights: HashMap::new(),
+        }
+    }
+
+    /// Builds the profile with the provided name from the config, if there is one
+    pub fn named(name: &str) -> Option<Self> {
+        let config = config::get();
+        let profile = config.profiles.get(name)?;
+
+        Some(Self {
+            name: Some(name.to_string()),
+            random: profile.random.unwrap_or(config.random),
+            tags: profile.tags.iter().cloned().collect(),
+            allowed_alts: profile
+                .allowed_alts
+                .iter()
+                .map(|(stage_name, alts)| (labels::hash(stage_name), alts.iter().copied().collect()))
+                .collect(),
+            weights: profile
+                .weights
+                .iter()
+                .map(|(stage_name, weights)| (labels::hash(stage_name), weights.clone()))
+                .collect(),
+        })
+    }
+
+    /// Checks if the profile allows the alt to be used for the stage. The vanilla stage is always allowed.
+    pub fn is_allowed(&self, stage_name: Hash40, alt_index: usize) -> bool {
+        alt_index == 0
+            || self
+                .allowed_alts
+                .get(&stage_name)
+                .map_or(true, |alts| alts.contains(&alt_index))
+    }
+
+    /// Gets the weight of the alt when it is randomly picked
+    pub fn weight(&self, stage_name: Hash40, alt_index: usize) -> u32 {
+        self.weights
+            .get(&stage_name)
+            .and_then(|weights| weights.get(alt_index))
+            .copied()
+            .unwrap_or(1)
+    }
+}
+
+/// A rule from the config for picking alts outside of the stage select screen, see [`config::RuleConfig`]
+pub struct AltRule {
+    pub mode: Option<GameMode>,
+    pub stage: Option<Hash40>,
+    pub spirit: Option<Hash40>,
+    pub alt: RuleAlt,
+}
+
+impl AltRule {
+    pub fn from_config(rule: &RuleConfig) -> Self {
+        Self {
+            mode: rule.mode,
+            stage: rule.stage.as_deref().map(labels::hash),
+            spirit: rule.spirit.as_deref().map(labels::hash),
+            alt: rule.alt,
+        }
+    }
+
+    /// Checks if each of the rule's conditions match
+    pub fn matches(&self, mode: GameMode, stage_name: Hash40, spirit: Option<Hash40>) -> bool {
+        self.mode.map_or(true, |rule_mode| rule_mode == mode)
+            && self.stage.map_or(true, |rule_stage| rule_stage == stage_name)
+            && self.spirit.map_or(true, |rule_spirit| Some(rule_spirit) == spirit)
+    }
+}
+
 pub struct StageAltManager {
     pub filepath_backup: BTreeMap<Hash40, u32>,
     pub path_backup: BTreeMap<Hash40, u32>,
     pub folder_backup: BTreeMap<Hash40, u32>,
     pub alt_infos: HashMap<Hash40, StageAltInfo>,
     pub alts: Vec<Arc<StageAlt>>,
+
+    /// The selections made on the stage select screen, one for each of its stage previews. There is more than one
+    /// when the match changes stages partway through, in which case the stages are loaded in order and then wrap around.
     pub selection: Vec<Selection>,
+
+    /// The index into [`StageAltManager::selection`] of the stage that was loaded last, or `usize::MAX` if no stage has
+    /// been loaded since the stage select screen was opened
     pub current_index: usize,
-    pub current_alt: Option<Arc<StageAlt>>,
-    pub is_online: bool,
+
+    /// The alts that are currently swapped in, keyed by the name of their stage. There can be more than one when the
+    /// match changes stages partway through, and since each alt only patches the folders of its own stage they can
+    /// all be active at the same time.
+    pub active_alts: HashMap<Hash40, Arc<StageAlt>>,
+
+    /// The game mode that stages are being loaded for, see [`GameMode`]
+    pub game_mode: GameMode,
+
+    /// Every alt that has been preloaded, keyed by the stage preview index that requested it.
+    ///
+    /// Each of these files has had its ref count incremented so that the resource service keeps it in memory until
+    /// the preload is either cancelled or released. Each folder of a preload is released on its own once its
+    /// directory has been swapped, so the folders that haven't been loaded yet keep their references.
+    pub preloaded_files: HashMap<usize, Preload>,
+
+    /// The preload changes that haven't been handled yet, see [`PreloadRequest`]
+    pub preload_requests: Vec<PreloadRequest>,
+
+    /// The policy for injecting files into the load lists, see [`LoadInjectionPolicy`]
+    pub injection_policy: LoadInjectionPolicy,
+
+    /// The alt used for each stage when there is no valid selection for it, from the config
+    pub default_alts: HashMap<Hash40, usize>,
+
+    /// The custom order of the alts for each stage, from the config. Every alt of the stage is present exactly once.
+    pub alt_orders: HashMap<Hash40, Vec<usize>>,
+
+    /// The active selection profile, see [`SelectionProfile`]
+    pub profile: SelectionProfile,
+
+    /// The alts of each stage that have been turned off for random on the stage select screen. This is saved to the
+    /// SD card whenever it changes.
+    pub random_disabled: HashMap<Hash40, HashSet<usize>>,
+
+    /// The active best-of series, see [`Series`]
+    pub series: Option<Series>,
+
+    /// The stage, alt and form that were loaded last, not counting the result stage
+    pub last_played: Option<(Hash40, usize, StageForm)>,
+
+    /// The alt of each stage that was loaded last
+    pub last_used_alts: HashMap<Hash40, usize>,
+
+    /// The result stage alt to use for each stage when the result stage policy is to match the played stage, from the
+    /// config. The alt of the stage is `None` for the entries that apply to every alt of the stage.
+    pub result_stage_alts: HashMap<(Hash40, Option<usize>), usize>,
+
+    /// The rules for picking alts when there is no selection from the stage select screen
+    pub rules: Vec<AltRule>,
+
+    /// The alt to use for each spirit, keyed by `ui_spirit_id`
+    pub spirit_alts: HashMap<Hash40, usize>,
+
+    /// The `ui_spirit_id` of the spirit that is being fought, if any. This is set when a spirit battle starts, which
+    /// can only be detected when the offset of the spirit battle hook is found in the signature file.
+    pub current_spirit: Option<Hash40>,
 }
 
 impl StageAltManager {
@@ -106,7 +359,37 @@ impl StageAltManager {
     //         .map(|index| index as u32)
     // }
 
-    fn vanilla_normal_ui_path(stage_name: Hash40) -> Hash40 {
+    /// Builds the path of a vanilla UI texture, `ui/<replace folder>/stage/<folder>/<folder>_<stage name>.bntx`.
+    ///
+    /// The paths are built every time a texture is looked up, so they are only labeled when `labeled` is set, which is
+    /// done once for each stage when the alts are discovered (see [`StageAltManager::register_vanilla_ui_labels`]).
+    fn vanilla_ui_path(replace_folder: Hash40, folder: &str, stage_name: Hash40, labeled: bool) -> Hash40 {
+        let prefix = format!("{}_", folder);
+        if labeled {
+            let file_name = Hash40::from(prefix.as_str()).concat_labeled(stage_name).concat_labeled(".bntx");
+            Hash40::from("ui")
+                .join_path_labeled(replace_folder)
+                .join_path_labeled("stage")
+                .join_path_labeled(folder)
+                .join_path_labeled(file_name)
+        } else {
+            let file_name = Hash40::from(prefix.as_str()).concat(stage_name).concat(".bntx");
+            Hash40::from("ui")
+                .join_path(replace_folder)
+                .join_path("stage")
+                .join_path(folder)
+                .join_path(file_name)
+        }
+    }
+
+    /// Registers the labels of the stage's vanilla UI paths, so that they show up in the logs
+    pub fn register_vanilla_ui_labels(stage_name: Hash40) {
+        Self::vanilla_normal_ui_path(stage_name, true);
+        Self::vanilla_battle_ui_path(stage_name, true);
+        Self::vanilla_end_ui_path(stage_name, true);
+    }
+
+    fn vanilla_normal_ui_path(stage_name: Hash40, labeled: bool) -> Hash40 {
         let replace_folder = if Self::is_dlc_stage(stage_name) {
             Hash40::from("replace_patch")
         } else {
@@ -121,16 +404,10 @@ impl StageAltManager {
             stage_name
         };
 
-        let file_name = Hash40::from("stage_2_").concat(stage_name).concat(".bntx");
-
-        Hash40::from("ui")
-            .join_path(replace_folder)
-            .join_path("stage")
-            .join_path("stage_2")
-            .join_path(file_name)
+        Self::vanilla_ui_path(replace_folder, "stage_2", stage_name, labeled)
     }
 
-    fn vanilla_battle_ui_path(stage_name: Hash40) -> Hash40 {
+    fn vanilla_battle_ui_path(stage_name: Hash40, labeled: bool) -> Hash40 {
         let replace_folder =
             if Self::is_dlc_stage(stage_name) && stage_name != Hash40::from("battlefield_s") {
                 Hash40::from("replace_patch")
@@ -146,16 +423,10 @@ impl StageAltManager {
             stage_name
         };
 
-        let file_name = Hash40::from("stage_4_").concat(stage_name).concat(".bntx");
-
-        Hash40::from("ui")
-            .join_path(replace_folder)
-            .join_path("stage")
-            .join_path("stage_4")
-            .join_path(file_name)
+        Self::vanilla_ui_path(replace_folder, "stage_4", stage_name, labeled)
     }
 
-    fn vanilla_end_ui_path(stage_name: Hash40) -> Hash40 {
+    fn vanilla_end_ui_path(stage_name: Hash40, labeled: bool) -> Hash40 {
         let replace_folder =
             if Self::is_dlc_stage(stage_name) && stage_name != Hash40::from("battlefield_s") {
                 Hash40::from("replace_patch")
@@ -171,170 +442,481 @@ impl StageAltManager {
             stage_name
         };
 
-        let file_name = Hash40::from("stage_3_").concat(stage_name).concat(".bntx");
+        Self::vanilla_ui_path(replace_folder, "stage_3", stage_name, labeled)
+    }
+
+    pub fn is_online(&self) -> bool {
+        self.game_mode.is_online()
+    }
+
+    pub fn set_game_mode(&mut self, mode: GameMode) {
+        if self.game_mode != mode {
+            info!("Switching the game mode from {} to {}", self.game_mode, mode);
+            self.game_mode = mode;
+            self.current_spirit = None;
+        }
+    }
+
+    /// Sets the spirit that is being fought, which the rules match against until the game mode changes
+    pub fn set_current_spirit(&mut self, spirit: Hash40) {
+        info!("Starting a battle against the spirit {}", spirit.label());
+        self.current_spirit = Some(spirit);
+    }
+
+    /// Switches between local smash and training, since training can only be detected once a stage is loading
+    pub fn update_training_mode(&mut self, is_training: bool) {
+        match self.game_mode {
+            GameMode::LocalSmash if is_training => self.set_game_mode(GameMode::Training),
+            GameMode::Training if !is_training => self.set_game_mode(GameMode::LocalSmash),
+            _ => {}
+        }
+    }
+
+    /// Checks if the alt is allowed by the active profile and passes its tag filter. The vanilla stage always passes,
+    /// and every alt passes the tag filter when there are no active tags.
+    pub fn is_alt_visible(&self, stage_name: Hash40, alt_index: usize, alt: &StageAlt) -> bool {
+        alt_index == 0
+            || (self.profile.is_allowed(stage_name, alt_index)
+                && (self.profile.tags.is_empty()
+                    || alt.tags.iter().any(|tag| self.profile.tags.contains(tag))))
+    }
 
-        Hash40::from("ui")
-            .join_path(replace_folder)
-            .join_path("stage")
-            .join_path("stage_3")
-            .join_path(file_name)
+    /// Checks if the alt can currently be used for the provided stage form, which means that it is visible with the
+    /// active profile and is wifi-safe if we are online
+    fn is_alt_usable(&self, stage_name: Hash40, alt_index: usize, alt: &StageAlt, form: StageForm) -> bool {
+        self.is_alt_visible(stage_name, alt_index, alt) && (!self.is_online() || alt.is_wifi_safe(form))
     }
 
-    pub fn get_next_alt(&self, stage_name: Hash40, current_index: usize, is_battle: bool) -> usize {
+    /// Gets the order that the alts of the stage are cycled through on the stage select screen.
+    ///
+    /// This is the custom order from the config if there is one, otherwise the folder order. The cycle starts at
+    /// the stage's default alt, so cycling forward past the last alt wraps around to the default.
+    pub fn get_alt_order(&self, stage_name: Hash40) -> Vec<usize> {
         let Some(info) = self.alt_infos.get(&stage_name) else {
-            info!("Stage {:#x} has no alts, resorting to default", stage_name.0);
-            return 0;
+            return vec![0];
         };
 
-        if let Some(alt) = info.alts_found.get(current_index + 1) {
-            if self.is_online
-                && ((is_battle && !alt.is_battle_ws) || (!is_battle && !alt.is_normal_ws))
-            {
-                info!(
-                    "Skipping alt {} for stage {:#x} because it is not wifi-safe",
-                    current_index + 1,
-                    stage_name.0
-                );
-                self.get_next_alt(stage_name, current_index + 1, is_battle)
-            } else {
-                info!(
-                    "Stage {:#x} has alt {}, using that one",
-                    stage_name.0,
-                    current_index + 1
-                );
-                current_index + 1
-            }
-        } else {
-            info!(
-                "Stage {:#x} did not have alt {}, using default",
-                stage_name.0,
-                current_index + 1
-            );
-            0
+        let mut order = self
+            .alt_orders
+            .get(&stage_name)
+            .cloned()
+            .unwrap_or_else(|| (0..info.alts_found.len().max(1)).collect());
+
+        if let Some(position) = order
+            .iter()
+            .position(|alt| *alt == self.get_default_alt(stage_name))
+        {
+            order.rotate_left(position);
         }
+
+        order
     }
 
-    pub fn get_prev_alt(&self, stage_name: Hash40, current_index: usize, is_battle: bool) -> usize {
+    /// Cycles through the alt order in the provided direction starting from the current alt, returning the first alt
+    /// that can be used for the form. The vanilla stage is used when no alt can be.
+    fn step_alt(&self, stage_name: Hash40, current_index: usize, form: StageForm, direction: CycleDirection) -> usize {
+        if self.is_online() && !config::get().allow_online {
+            info!("Alts are not allowed online, using the vanilla stage for {}", stage_name.label());
+            return 0;
+        }
+
         let Some(info) = self.alt_infos.get(&stage_name) else {
-            info!("Stage {:#x} has no alts, resorting to default", stage_name.0);
+            info!("Stage {} has no alts, resorting to default", stage_name.label());
             return 0;
         };
 
-        if let Some(alt) = info.alts_found.get(current_index - 1) {
-            if self.is_online
-                && ((is_battle && !alt.is_battle_ws) || (!is_battle && !alt.is_normal_ws))
-            {
-                info!(
-                    "Skipping alt {} for stage {:#x} because it is not wifi-safe",
-                    current_index - 1,
-                    stage_name.0
-                );
-                self.get_prev_alt(stage_name, current_index - 1, is_battle)
-            } else {
+        let order = self.get_alt_order(stage_name);
+
+        if !order.contains(&current_index) {
+            info!(
+                "Stage {} did not have alt {}, starting from the default",
+                stage_name.label(),
+                current_index
+            );
+        }
+
+        let next = cycle_alt(&order, current_index, direction, |next| {
+            let usable = info
+                .alts_found
+                .get(next)
+                .map_or(false, |alt| self.is_alt_usable(stage_name, next, alt, form));
+
+            if !usable {
                 info!(
-                    "Stage {:#x} has alt {}, using that one",
-                    stage_name.0,
-                    current_index - 1
+                    "Skipping alt {} for stage {} @ {} because it is filtered out or not wifi-safe",
+                    next,
+                    stage_name.label(),
+                    form
                 );
-                current_index - 1
             }
-        } else {
-            info!(
-                "Stage {:#x} did not have alt {}, using default",
-                stage_name.0,
-                current_index - 1
-            );
-            self.get_prev_alt(stage_name, info.alts_found.len(), is_battle)
+
+            usable
+        });
+
+        match next {
+            Some(next) => {
+                info!("Stage {} has alt {}, using that one", stage_name.label(), next);
+                next
+            }
+            None => {
+                error!("No alt of stage {} can be used @ {}, using the vanilla stage", stage_name.label(), form);
+                0
+            }
+        }
+    }
+
+    pub fn get_next_alt(&self, stage_name: Hash40, current_index: usize, form: StageForm) -> usize {
+        self.step_alt(stage_name, current_index, form, CycleDirection::Forward)
+    }
+
+    pub fn get_prev_alt(&self, stage_name: Hash40, current_index: usize, form: StageForm) -> usize {
+        self.step_alt(stage_name, current_index, form, CycleDirection::Backward)
+    }
+
+    /// Gets the alt to show after the form of a preview is switched. The current alt is kept if it can be used for the
+    /// new form, otherwise this moves on to the next alt in the order that can be.
+    pub fn resolve_alt_for_form(&self, stage_name: Hash40, current_index: usize, form: StageForm) -> usize {
+        let is_usable = (!self.is_online() || config::get().allow_online)
+            && self
+                .alt_infos
+                .get(&stage_name)
+                .and_then(|info| info.alts_found.get(current_index))
+                .map_or(false, |alt| self.is_alt_usable(stage_name, current_index, alt, form));
+
+        if is_usable {
+            info!("Keeping alt {} for {} @ {}", current_index, stage_name.label(), form);
+            return current_index;
         }
+
+        self.get_next_alt(stage_name, current_index, form)
     }
 
-    pub fn get_random_alt(&self, stage_name: Hash40, is_battle: bool) -> usize {
+    /// Gets the alts of the stage that can be randomly picked for the form, which is empty when only the vanilla
+    /// stage can be used
+    fn get_random_candidates(&self, stage_name: Hash40, form: StageForm) -> Vec<usize> {
+        let config = config::get();
+        let policy = self.profile.random;
+
+        if policy == RandomPolicy::Vanilla {
+            info!("The random policy is vanilla, so no random alt will be picked for {}", stage_name.label());
+            return vec![];
+        }
+
+        if self.is_online() && !config.allow_online {
+            info!("Alts are not allowed online, so no random alt will be picked for {}", stage_name.label());
+            return vec![];
+        }
+
         let Some(info) = self.alt_infos.get(&stage_name) else {
-            info!("Stage {:#x} has no alts, so no random alt will be picked", stage_name.0);
-            return 0;
+            info!("Stage {} has no alts, so no random alt will be picked", stage_name.label());
+            return vec![];
         };
 
         if info.alts_found.is_empty() {
-            error!("Stage {:#x} has no alts, despite having alt information. No random alt will be picked", stage_name.0);
-            return 0;
+            error!("Stage {} has no alts, despite having alt information. No random alt will be picked", stage_name.label());
+            return vec![];
         }
 
-        let alt = loop {
-            let alt = rand::random::<usize>() % info.alts_found.len();
-            if is_battle {
-                if self.is_online && !info.alts_found[alt].is_battle_ws {
+        let mut candidates: Vec<usize> = (0..info.alts_found.len())
+            .filter(|alt| {
+                let stage_alt = &info.alts_found[*alt];
+
+                if self.is_online() && !stage_alt.is_wifi_safe(form) {
                     info!(
-                        "Skipping random alt {} for {:#x} because it is not wifi safe!",
-                        alt, stage_name.0
+                        "Skipping random alt {} for {} @ {} because it is not wifi safe!",
+                        alt, stage_name.label(), form
                     );
-                    continue;
+                    return false;
                 }
 
-                if info.alts_found[alt].is_battle_ignore {
+                if stage_alt.is_ignored(form) {
                     info!(
-                        "Skipping random alt {} for {:#x} because it should be ignored!",
-                        alt, stage_name.0
+                        "Skipping random alt {} for {} @ {} because it should be ignored!",
+                        alt, stage_name.label(), form
                     );
-                    continue;
+                    return false;
                 }
-            } else {
-                if self.is_online && !info.alts_found[alt].is_normal_ws {
+
+                if !self.is_alt_visible(stage_name, *alt, stage_alt) {
                     info!(
-                        "Skipping random alt {} for {:#x} because it is not wifi safe!",
-                        alt, stage_name.0
+                        "Skipping random alt {} for {} because it is not allowed by the active profile!",
+                        alt, stage_name.label()
                     );
-                    continue;
+                    return false;
                 }
 
-                if info.alts_found[alt].is_normal_ignore {
+                if !self.is_random_enabled(stage_name, *alt) {
                     info!(
-                        "Skipping random alt {} for {:#x} because it should be ignored!",
-                        alt, stage_name.0
+                        "Skipping random alt {} for {} because it has been turned off for random!",
+                        alt, stage_name.label()
                     );
-                    continue;
+                    return false;
                 }
-            }
 
-            break alt;
+                if self.profile.weight(stage_name, *alt) == 0 {
+                    info!(
+                        "Skipping random alt {} for {} because its weight is 0!",
+                        alt, stage_name.label()
+                    );
+                    return false;
+                }
+
+                true
+            })
+            .collect();
+
+        if policy == RandomPolicy::AltsOnly && candidates.iter().any(|alt| *alt != 0) {
+            candidates.retain(|alt| *alt != 0);
+        }
+
+        candidates
+    }
+
+    pub fn get_random_alt(&self, stage_name: Hash40, form: StageForm) -> usize {
+        let candidates = self.get_random_candidates(stage_name, form);
+
+        let Some(alt) = pick_weighted(
+            &candidates,
+            |alt| self.profile.weight(stage_name, alt) as u64,
+            rand::random(),
+        ) else {
+            info!("There are no alts for {} that can be randomly picked", stage_name.label());
+            return 0;
         };
 
-        info!("Using random alt for stage {:#x}: {}", stage_name.0, alt);
+        info!("Using random alt for stage {}: {}", stage_name.label(), alt);
         alt
     }
 
+    /// Randomly picks a stage and alt out of every alt of the provided stages, so that a stage with more alts is more
+    /// likely to be picked. The stages are paired with the id of their panel on the stage select screen, which is
+    /// returned along with the alt. A stage is left out of the pool entirely when all of its alts, including the
+    /// vanilla stage, have been turned off for random.
+    pub fn get_random_pool_entry(
+        &self,
+        stages: &[(usize, Hash40)],
+        form: StageForm,
+    ) -> Option<(usize, Hash40, usize)> {
+        let pool: Vec<(usize, Hash40, usize)> = stages
+            .iter()
+            .flat_map(|(panel_id, stage_name)| {
+                let mut candidates = self.get_random_candidates(*stage_name, form);
+                if candidates.is_empty() {
+                    if self.is_random_enabled(*stage_name, 0) {
+                        candidates.push(0);
+                    } else {
+                        info!("Leaving {} out of the random pool because it has been turned off for random", stage_name.label());
+                    }
+                }
+
+                candidates
+                    .into_iter()
+                    .map(move |alt| (*panel_id, *stage_name, alt))
+            })
+            .collect();
+
+        info!("Picking a random stage out of a pool of {} alts", pool.len());
+
+        pick_weighted(
+            &pool,
+            |(_, stage_name, alt)| self.profile.weight(stage_name, alt) as u64,
+            rand::random(),
+        )
+    }
+
+    /// Resolves the per-stage settings of the config against the discovered alts, logging the settings for stages or
+    /// alts that don't exist. This is called once the alts have been discovered.
+    pub fn resolve_config(&mut self) {
+        self.default_alts = self.resolve_default_alts();
+        self.alt_orders = self.resolve_alt_orders();
+        self.result_stage_alts = self.resolve_result_stage_alts();
+        self.check_profiles();
+    }
+
+    fn resolve_default_alts(&self) -> HashMap<Hash40, usize> {
+        let mut default_alts = HashMap::new();
+        for (stage_name, alt) in config::get().default_alts.iter() {
+            let stage_hash = labels::hash(stage_name);
+            let Some(info) = self.alt_infos.get(&stage_hash) else {
+                error!("The config has a default alt for {}, but that stage does not exist", stage_name);
+                continue;
+            };
+
+            if *alt != 0 && *alt >= info.alts_found.len() {
+                error!("The config has default alt {} for {}, but that alt does not exist", alt, stage_name);
+                continue;
+            }
+
+            default_alts.insert(stage_hash, *alt);
+        }
+
+        default_alts
+    }
+
+    /// Resolves the alt orders from the config, leaving out the alts that don't exist and appending the alts that are
+    /// missing from the order, so that every alt of the stage is present exactly once
+    fn resolve_alt_orders(&self) -> HashMap<Hash40, Vec<usize>> {
+        let mut alt_orders = HashMap::new();
+        for (stage_name, order) in config::get().alt_order.iter() {
+            let stage_hash = labels::hash(stage_name);
+            let Some(info) = self.alt_infos.get(&stage_hash) else {
+                error!("The config has an alt order for {}, but that stage does not exist", stage_name);
+                continue;
+            };
+
+            let count = info.alts_found.len();
+
+            let mut full_order: Vec<usize> = vec![];
+            for alt in order.iter().copied() {
+                if alt >= count {
+                    error!("The alt order for {} has alt {}, but that alt does not exist", stage_name, alt);
+                } else if full_order.contains(&alt) {
+                    error!("The alt order for {} has alt {} more than once", stage_name, alt);
+                } else {
+                    full_order.push(alt);
+                }
+            }
+
+            for alt in 0..count {
+                if !full_order.contains(&alt) {
+                    full_order.push(alt);
+                }
+            }
+
+            alt_orders.insert(stage_hash, full_order);
+        }
+
+        alt_orders
+    }
+
+    /// Resolves the result stage alts from the config, which are keyed by either `<stage>` or `<stage>:<alt>`
+    fn resolve_result_stage_alts(&self) -> HashMap<(Hash40, Option<usize>), usize> {
+        let result_stage_count = self
+            .alt_infos
+            .get(&Hash40::from("resultstage"))
+            .map_or(0, |info| info.alts_found.len());
+
+        if let ResultStagePolicy::Fixed(alt) = config::get().result_stage {
+            if alt != 0 && alt >= result_stage_count {
+                error!("The config uses result stage alt {}, but that alt does not exist", alt);
+            }
+        }
+
+        let mut result_stage_alts = HashMap::new();
+        for (key, result_alt) in config::get().result_stage_alts.iter() {
+            let (stage_name, stage_alt) = match key.split_once(':') {
+                Some((stage_name, stage_alt)) => {
+                    let Ok(stage_alt) = stage_alt.parse::<usize>() else {
+                        error!("The config has a result stage alt for {}, but {} is not an alt number", key, stage_alt);
+                        continue;
+                    };
+                    (stage_name, Some(stage_alt))
+                }
+                None => (key.as_str(), None),
+            };
+
+            let stage_hash = labels::hash(stage_name);
+            let Some(info) = self.alt_infos.get(&stage_hash) else {
+                error!("The config has a result stage alt for {}, but that stage does not exist", stage_name);
+                continue;
+            };
+
+            if let Some(stage_alt) = stage_alt {
+                if stage_alt != 0 && stage_alt >= info.alts_found.len() {
+                    error!(
+                        "The config has a result stage alt for {}, but {} does not have alt {}",
+                        key, stage_name, stage_alt
+                    );
+                    continue;
+                }
+            }
+
+            if *result_alt != 0 && *result_alt >= result_stage_count {
+                error!("The config has result stage alt {} for {}, but that alt does not exist", result_alt, key);
+                continue;
+            }
+
+            result_stage_alts.insert((stage_hash, stage_alt), *result_alt);
+        }
+
+        result_stage_alts
+    }
+
+    /// Logs the settings of the config's profiles that are for stages that don't exist
+    fn check_profiles(&self) {
+        for (profile_name, profile) in config::get().profiles.iter() {
+            for stage_name in profile.allowed_alts.keys().chain(profile.weights.keys()) {
+                if !self.alt_infos.contains_key(&labels::hash(stage_name)) {
+                    error!("The profile {} has settings for {}, but that stage does not exist", profile_name, stage_name);
+                }
+            }
+        }
+    }
+
+    /// Gets the alt that is used for the stage when there is no valid selection for it, and that is
+    /// initially shown on the stage select screen
+    pub fn get_default_alt(&self, stage_name: Hash40) -> usize {
+        self.default_alts.get(&stage_name).copied().unwrap_or(0)
+    }
+
+    /// Gets the alt of the stage that was loaded last, or its default alt if it hasn't been loaded yet
+    pub fn get_last_used_alt(&self, stage_name: Hash40) -> usize {
+        self.last_used_alts
+            .get(&stage_name)
+            .copied()
+            .unwrap_or_else(|| self.get_default_alt(stage_name))
+    }
+
+    /// Gets the default alt for the stage if it can currently be used for the provided stage form, otherwise the vanilla stage
+    pub fn get_usable_default_alt(&self, stage_name: Hash40, form: StageForm) -> usize {
+        if self.is_online() && !config::get().allow_online {
+            return 0;
+        }
+
+        let default = self.get_default_alt(stage_name);
+        match self
+            .alt_infos
+            .get(&stage_name)
+            .and_then(|info| info.alts_found.get(default))
+        {
+            Some(alt) if self.is_alt_usable(stage_name, default, alt, form) => default,
+            _ => 0,
+        }
+    }
+
     pub fn get_normal_ui_path(&self, stage_name: Hash40, alt: usize) -> Hash40 {
         if alt == 0 {
-            let path = Self::vanilla_normal_ui_path(stage_name);
+            let path = Self::vanilla_normal_ui_path(stage_name, false);
             info!(
-                "Getting default UI path for {:#x} @ normal: {:#x}",
-                stage_name.0, path.0
+                "Getting default UI path for {} @ normal: {}",
+                stage_name.label(), path.label()
             );
             return path;
         }
 
         let Some(info) = self.alt_infos.get(&stage_name) else {
-            let path = Self::vanilla_normal_ui_path(stage_name);
-            error!("There is no stage alt information for {:#x}, using vanilla file path for normal: {:#x}", stage_name.0, path.0);
+            let path = Self::vanilla_normal_ui_path(stage_name, false);
+            error!("There is no stage alt information for {}, using vanilla file path for normal: {}", stage_name.label(), path.label());
             return path;
         };
 
         if info.alts_found.is_empty() {
-            let path = Self::vanilla_normal_ui_path(stage_name);
-            error!("There are no stage alts for the stage {:#x}, using vanilla file path for normal: {:#x}", stage_name.0, path.0);
+            let path = Self::vanilla_normal_ui_path(stage_name, false);
+            error!("There are no stage alts for the stage {}, using vanilla file path for normal: {}", stage_name.label(), path.label());
             return path;
         }
 
         if let Some(stage_alt) = info.alts_found.get(alt) {
             info!(
-                "Getting alt #{} UI path for {:#x} @ normal: {:#x}",
-                alt, stage_name.0, stage_alt.ui_paths[2].0
+                "Getting alt #{} UI path for {} @ normal: {}",
+                alt, stage_name.label(), stage_alt.ui_paths[2].label()
             );
             stage_alt.ui_paths[2]
         } else {
-            let path = Self::vanilla_normal_ui_path(stage_name);
+            let path = Self::vanilla_normal_ui_path(stage_name, false);
             error!(
-                "There is no stage alt #{} for {:#x}, using vanilla file path for normal: {:#x}",
-                alt, stage_name.0, path.0
+                "There is no stage alt #{} for {}, using vanilla file path for normal: {}",
+                alt, stage_name.label(), path.label()
             );
             path
         }
@@ -342,37 +924,37 @@ impl StageAltManager {
 
     pub fn get_battle_ui_path(&self, stage_name: Hash40, alt: usize) -> Hash40 {
         if alt == 0 {
-            let path = Self::vanilla_battle_ui_path(stage_name);
+            let path = Self::vanilla_battle_ui_path(stage_name, false);
             info!(
-                "Getting default UI path for {:#x} @ battle: {:#x}",
-                stage_name.0, path.0
+                "Getting default UI path for {} @ battle: {}",
+                stage_name.label(), path.label()
             );
             return path;
         }
 
         let Some(info) = self.alt_infos.get(&stage_name) else {
-            let path = Self::vanilla_battle_ui_path(stage_name);
-            error!("There is no stage alt information for {:#x}, using vanilla file path for battle: {:#x}", stage_name.0, path.0);
+            let path = Self::vanilla_battle_ui_path(stage_name, false);
+            error!("There is no stage alt information for {}, using vanilla file path for battle: {}", stage_name.label(), path.label());
             return path;
         };
 
         if info.alts_found.is_empty() {
-            let path = Self::vanilla_battle_ui_path(stage_name);
-            error!("There are no stage alts for the stage {:#x}, using vanilla file path for battle: {:#x}", stage_name.0, path.0);
+            let path = Self::vanilla_battle_ui_path(stage_name, false);
+            error!("There are no stage alts for the stage {}, using vanilla file path for battle: {}", stage_name.label(), path.label());
             return path;
         }
 
         if let Some(stage_alt) = info.alts_found.get(alt) {
             info!(
-                "Getting alt #{} UI path for {:#x} @ battle: {:#x}",
-                alt, stage_name.0, stage_alt.ui_paths[4].0
+                "Getting alt #{} UI path for {} @ battle: {}",
+                alt, stage_name.label(), stage_alt.ui_paths[4].label()
             );
             stage_alt.ui_paths[4]
         } else {
-            let path = Self::vanilla_battle_ui_path(stage_name);
+            let path = Self::vanilla_battle_ui_path(stage_name, false);
             error!(
-                "There is no stage alt #{} for {:#x}, using vanilla file path for battle: {:#x}",
-                alt, stage_name.0, path.0
+                "There is no stage alt #{} for {}, using vanilla file path for battle: {}",
+                alt, stage_name.label(), path.label()
             );
             path
         }
@@ -380,42 +962,131 @@ impl StageAltManager {
 
     pub fn get_end_ui_path(&self, stage_name: Hash40, alt: usize) -> Hash40 {
         if alt == 0 {
-            let path = Self::vanilla_end_ui_path(stage_name);
+            let path = Self::vanilla_end_ui_path(stage_name, false);
             info!(
-                "Getting default UI path for {:#x} @ end: {:#x}",
-                stage_name.0, path.0
+                "Getting default UI path for {} @ end: {}",
+                stage_name.label(), path.label()
             );
             return path;
         }
 
         let Some(info) = self.alt_infos.get(&stage_name) else {
-            let path = Self::vanilla_end_ui_path(stage_name);
-            error!("There is no stage alt information for {:#x}, using vanilla file path for end: {:#x}", stage_name.0, path.0);
+            let path = Self::vanilla_end_ui_path(stage_name, false);
+            error!("There is no stage alt information for {}, using vanilla file path for end: {}", stage_name.label(), path.label());
             return path;
         };
 
         if info.alts_found.is_empty() {
-            let path = Self::vanilla_end_ui_path(stage_name);
-            error!("There are no stage alts for the stage {:#x}, using vanilla file path for end: {:#x}", stage_name.0, path.0);
+            let path = Self::vanilla_end_ui_path(stage_name, false);
+            error!("There are no stage alts for the stage {}, using vanilla file path for end: {}", stage_name.label(), path.label());
             return path;
         }
 
         if let Some(stage_alt) = info.alts_found.get(alt) {
             info!(
-                "Getting alt #{} UI path for {:#x} @ end: {:#x}",
-                alt, stage_name.0, stage_alt.ui_paths[3].0
+                "Getting alt #{} UI path for {} @ end: {}",
+                alt, stage_name.label(), stage_alt.ui_paths[3].label()
             );
             stage_alt.ui_paths[3]
         } else {
-            let path = Self::vanilla_end_ui_path(stage_name);
+            let path = Self::vanilla_end_ui_path(stage_name, false);
             error!(
-                "There is no stage alt #{} for {:#x}, using vanilla file path for end: {:#x}",
-                alt, stage_name.0, path.0
+                "There is no stage alt #{} for {}, using vanilla file path for end: {}",
+                alt, stage_name.label(), path.label()
             );
             path
         }
     }
 
+    /// Checks if the alt has not been turned off for random
+    pub fn is_random_enabled(&self, stage_name: Hash40, alt: usize) -> bool {
+        self.random_disabled
+            .get(&stage_name)
+            .map_or(true, |alts| !alts.contains(&alt))
+    }
+
+    /// Turns the alt on or off for random and saves the change. Returns whether it was saved.
+    pub fn set_random_enabled(&mut self, stage_name: Hash40, alt: usize, enabled: bool) -> bool {
+        info!(
+            "Turning random {} for {} @ {}",
+            if enabled { "on" } else { "off" },
+            stage_name.label(),
+            alt
+        );
+
+        let alts = self.random_disabled.entry(stage_name).or_default();
+        if enabled {
+            alts.remove(&alt);
+        } else {
+            alts.insert(alt);
+        }
+
+        random_toggles::save(&self.random_disabled)
+    }
+
+    /// Switches to the named profile from the config, or to the top-level settings if there is no name.
+    /// Returns whether the profile exists.
+    pub fn set_profile(&mut self, name: Option<&str>) -> bool {
+        let profile = match name {
+            Some(name) => {
+                let Some(profile) = SelectionProfile::named(name) else {
+                    error!("There is no profile named {}, keeping the current profile", name);
+                    return false;
+                };
+                profile
+            }
+            None => SelectionProfile::base(),
+        };
+
+        info!("Switching to the selection profile {}", name.unwrap_or("<none>"));
+        self.profile = profile;
+        true
+    }
+
+    /// Resets the selection index so that the next stage that is loaded uses the first selection
+    pub fn reset_selection_index(&mut self) {
+        self.current_index = usize::MAX;
+    }
+
+    pub fn start_series(&mut self, game_count: usize) {
+        info!("Starting a series of {} games", game_count);
+        self.series = Some(Series::new(game_count));
+    }
+
+    pub fn end_series(&mut self) {
+        if let Some(series) = self.series.take() {
+            info!("Ending the series after {} games", series.played);
+        }
+    }
+
+    /// Sets the predetermined selection for a game of the series, returning whether there is a series with that game
+    pub fn set_series_selection(&mut self, game: usize, selection: Selection) -> bool {
+        let Some(series) = self.series.as_mut() else {
+            error!("Cannot set the selection for game {} since there is no series", game);
+            return false;
+        };
+
+        let Some(entry) = series.games.get_mut(game) else {
+            error!("Cannot set the selection for game {} since the series only has {} games", game, series.games.len());
+            return false;
+        };
+
+        info!("Setting the selection for game {} of the series to {}", game, selection);
+        *entry = Some(selection);
+        true
+    }
+
+    /// Gets the selection for the first stage of a match. When there is a series, this starts its next game and uses
+    /// its predetermined selection if it has one for the incoming stage.
+    fn start_game(&mut self, incoming: Hash40) -> Selection {
+        let selection = self.selection[0];
+
+        match self.series.as_mut() {
+            Some(series) => series.start_game(incoming, selection),
+            None => selection,
+        }
+    }
+
     pub fn set_stage_use_count(&mut self, count: usize) {
         info!("Setting selection count to {}", count);
         self.selection = vec![Selection::Invalid; count];
@@ -431,7 +1102,8 @@ impl StageAltManager {
         self.selection[index] = selection;
     }
 
-    fn change_alt(&mut self, new_alt: Option<Arc<StageAlt>>) {
+    /// Swaps the active alt of the stage, unpatching the lookups of its previous alt before patching the new one
+    fn change_alt(&mut self, stage_name: Hash40, new_alt: Option<Arc<StageAlt>>) {
         // let arc = FilesystemInfo::instance().unwrap().arc();
         // if let Some(alt) = self.current_alt.take() {
         //     for (hash, (base, _)) in alt.sharing_base.iter() {
@@ -443,121 +1115,332 @@ impl StageAltManager {
         //         }
         //     }
         // }
-        self.unhack_lookups_for_alt();
-        self.current_alt = new_alt;
-        self.hack_lookups_for_alt();
+        if let Some(alt) = self.active_alts.remove(&stage_name) {
+            info!("Replacing the active alt of {}", stage_name.label());
+            self.unhack_lookups_for_alt(&alt);
+        }
+
+        if let Some(alt) = new_alt {
+            self.hack_lookups_for_alt(&alt);
+            self.active_alts.insert(stage_name, alt);
+        }
+    }
+
+    /// Unpatches the lookups of every active alt. This is done once the stages are no longer loaded, such as when we are
+    /// back on the stage select screen.
+    pub fn clear_active_alts(&mut self) {
+        for (stage_name, alt) in std::mem::take(&mut self.active_alts) {
+            info!("Deactivating the alt of {}", stage_name.label());
+            self.unhack_lookups_for_alt(&alt);
+        }
+    }
+
+    /// Unpatches the lookups of the alts of every stage other than the provided one and the result stage, since they
+    /// are no longer loaded. Modes without a stage select screen never clear the active alts otherwise, so they would
+    /// keep piling up from match to match.
+    fn deactivate_other_alts(&mut self, stage_name: Hash40) {
+        let result_stage = Hash40::from("resultstage");
+        let others: Vec<Hash40> = self
+            .active_alts
+            .keys()
+            .copied()
+            .filter(|other| *other != stage_name && *other != result_stage)
+            .collect();
+
+        for other in others {
+            if let Some(alt) = self.active_alts.remove(&other) {
+                info!("Deactivating the alt of {} since {} is loading", other.label(), stage_name.label());
+                self.unhack_lookups_for_alt(&alt);
+            }
+        }
     }
 
-    pub fn advance_alt(&mut self, incoming: Hash40, is_battle: bool) {
+    /// Gets the selection for the result stage, based on the result stage policy and the stage that was last played
+    fn get_result_stage_selection(&self) -> Selection {
+        let name = Hash40::from("resultstage");
+
+        match config::get().result_stage {
+            ResultStagePolicy::Random => {
+                info!("The result stage is incoming, selecting a random alt!");
+                Selection::Random { form: None }
+            }
+            ResultStagePolicy::Vanilla => {
+                info!("The result stage is incoming, using the vanilla stage!");
+                Selection::Regular { name, alt: 0 }
+            }
+            ResultStagePolicy::Fixed(alt) => {
+                info!("The result stage is incoming, using the fixed alt {}!", alt);
+                Selection::Regular { name, alt }
+            }
+            ResultStagePolicy::Match => {
+                let Some((stage_name, stage_alt, _)) = self.last_played else {
+                    info!("The result stage is incoming, but no stage has been played. Using the vanilla stage!");
+                    return Selection::Regular { name, alt: 0 };
+                };
+
+                let alt = self
+                    .result_stage_alts
+                    .get(&(stage_name, Some(stage_alt)))
+                    .or_else(|| self.result_stage_alts.get(&(stage_name, None)))
+                    .copied()
+                    .unwrap_or(0);
+
+                info!(
+                    "The result stage is incoming, using alt {} to match {} @ {}!",
+                    alt, stage_name.label(), stage_alt
+                );
+                Selection::Regular { name, alt }
+            }
+        }
+    }
+
+    /// Gets the selection from the first rule that matches the incoming stage, if there is one
+    fn get_rule_selection(&self, incoming: Hash40, form: StageForm) -> Option<Selection> {
+        for (index, rule) in self.rules.iter().enumerate() {
+            if !rule.matches(self.game_mode, incoming, self.current_spirit) {
+                continue;
+            }
+
+            let alt = match rule.alt {
+                RuleAlt::Fixed(alt) => alt,
+                RuleAlt::Random => return Some(Selection::Random { form: None }),
+                RuleAlt::Vanilla => 0,
+                RuleAlt::Default => self.get_usable_default_alt(incoming, form),
+                RuleAlt::Spirit => {
+                    let Some(alt) = self
+                        .current_spirit
+                        .and_then(|spirit| self.spirit_alts.get(&spirit))
+                        .copied() else {
+                        continue;
+                    };
+                    alt
+                }
+            };
+
+            info!("Rule #{} matched {}, using alt {}", index, incoming.label(), alt);
+            return Some(Selection::Regular {
+                name: incoming,
+                alt,
+            });
+        }
+
+        None
+    }
+
+    /// Cycles the alt of the stage that was played last and swaps it in, so that alts can be tested in training mode
+    /// without going back to the stage select screen. Returns whether the alt changed.
+    ///
+    /// The vanilla stage is swapped in as its own alt instead of clearing the active alt, so that the loaded
+    /// directories get their vanilla files back when they are swapped again.
+    pub fn hot_swap_alt(&mut self, direction: CycleDirection) -> bool {
+        let Some((stage_name, current, form)) = self.last_played else {
+            error!("Unable to hot swap the alt since no stage has been played");
+            return false;
+        };
+
+        let next = self.step_alt(stage_name, current, form, direction);
+        if next == current {
+            info!("Stage {} has no other alt to hot swap to", stage_name.label());
+            return false;
+        }
+
+        let Some(alt) = self
+            .alt_infos
+            .get(&stage_name)
+            .and_then(|info| info.alts_found.get(next))
+            .cloned() else {
+            error!("Unable to hot swap to alt {} for stage {} because it does not exist", next, stage_name.label());
+            return false;
+        };
+
+        info!("Hot swapping {} @ {} from alt {} to alt {}", stage_name.label(), form, current, next);
+        self.change_alt(stage_name, Some(alt));
+        self.last_played = Some((stage_name, next, form));
+        self.last_used_alts.insert(stage_name, next);
+
+        // The stage keeps the files it has already loaded, so the selection is updated as well to keep the new alt
+        // when the stage gets loaded again
+        if let Some(selection) = self.selection.get_mut(self.current_index) {
+            *selection = Selection::Regular {
+                name: stage_name,
+                alt: next,
+            };
+        }
+
+        true
+    }
+
+    /// Replaces the selection according to the policy of the current game mode, if it has one
+    fn apply_mode_policy(&self, incoming: Hash40, form: StageForm, selection: Selection) -> Selection {
+        let Some(policy) = config::get().mode_policies.get(&self.game_mode).copied() else {
+            return selection;
+        };
+
+        info!("Using the {:?} policy for {}", policy, self.game_mode);
+
+        match policy {
+            ModePolicy::Selection => selection,
+            ModePolicy::Vanilla => Selection::Regular {
+                name: incoming,
+                alt: 0,
+            },
+            ModePolicy::Random => Selection::Random { form: None },
+            ModePolicy::Default => Selection::Regular {
+                name: incoming,
+                alt: self.get_usable_default_alt(incoming, form),
+            },
+        }
+    }
+
+    /// Advances to the next selection and swaps in its alt for the incoming stage.
+    ///
+    /// The loading form can only tell the normal form apart from the others, since the battlefield and Ω forms are
+    /// loaded from the same folder.
+    pub fn advance_alt(&mut self, incoming: Hash40, form: StageForm) {
         info!("Advancing the alt to the next selection");
-        let sel = if self.selection.is_empty() {
-            info!("The selection list is empty, a random alt will be selected!");
-            Selection::Random
-        } else if incoming == Hash40::from("resultstage") {
-            info!("The result stage is incoming, selecting a random alt!");
-            Selection::Random
+        let is_result_stage = incoming == Hash40::from("resultstage");
+        let mut is_from_rule = false;
+        let sel = if is_result_stage {
+            self.get_result_stage_selection()
+        } else if self.selection.is_empty() {
+            match self.get_rule_selection(incoming, form) {
+                Some(sel) => {
+                    is_from_rule = true;
+                    sel
+                }
+                None => {
+                    info!("The selection list is empty, a random alt will be selected!");
+                    Selection::Random { form: None }
+                }
+            }
         } else if self.current_index == usize::MAX {
             self.current_index = 0;
-            self.selection[self.current_index]
+            self.start_game(incoming)
         } else {
             self.current_index = (self.current_index + 1) % self.selection.len();
             self.selection[self.current_index]
         };
 
-        match sel {
+        let sel = if is_result_stage || is_from_rule {
+            sel
+        } else {
+            self.apply_mode_policy(incoming, form, sel)
+        };
+
+        let (name, alt) = match sel {
             Selection::Invalid => {
+                let alt_id = self.get_usable_default_alt(incoming, form);
                 error!(
-                    "Invalid selection encountered when advancing alt with incoming {:#x}",
-                    incoming.0
+                    "Invalid selection encountered when advancing alt with incoming {}, using default alt {}",
+                    incoming.label(),
+                    alt_id
                 );
-                self.change_alt(None);
+
+                (incoming, alt_id)
             }
-            Selection::Random => {
-                let alt_id = self.get_random_alt(incoming, is_battle);
-                info!(
-                    "Randomly selected alt id {} for stage {:#x}",
-                    alt_id, incoming.0
-                );
-                if alt_id == 0 {
-                    info!("Since the alt id is 0, there will be no alt");
-                    self.change_alt(None);
-                    return;
-                }
+            Selection::Random { form: requested } => {
+                let form = match requested {
+                    Some(requested) if requested.is_battle() != form.is_battle() => {
+                        error!(
+                            "The random panel requested the {} form, but the {} form of {} is loading. Using the loading form",
+                            requested, form, incoming.label()
+                        );
+                        form
+                    }
+                    Some(requested) => requested,
+                    None => form,
+                };
 
-                self.change_alt(
-                    self.alt_infos
-                        .get(&incoming)
-                        .and_then(|info| info.alts_found.get(alt_id))
-                        .cloned(),
+                let alt_id = self.get_random_alt(incoming, form);
+                info!(
+                    "Randomly selected alt id {} for stage {} @ {}",
+                    alt_id, incoming.label(), form
                 );
 
-                if self.current_alt.is_none() {
-                    error!("Unable to use alt {} for stage {:#x}", alt_id, incoming.0);
-                }
+                (incoming, alt_id)
             }
             Selection::Regular { name, alt } => {
                 if name != incoming {
                     error!(
-                        "The incoming stage {:#x} did not match the reserved stage name {:#x}",
-                        incoming.0, name.0
+                        "The incoming stage {} did not match the reserved stage name {}",
+                        incoming.label(), name.label()
                     );
                 }
 
-                info!("Selecting alt {} for stage {:#x}", alt, name.0);
-                if alt == 0 {
-                    info!("Since the alt id is 0, there will be no alt");
-                    self.change_alt(None);
-                }
+                info!("Selecting alt {} for stage {}", alt, name.label());
+                (name, alt)
+            }
+        };
 
-                self.change_alt(
-                    self.alt_infos
-                        .get(&name)
-                        .and_then(|info| info.alts_found.get(alt))
-                        .cloned(),
-                );
+        if !is_result_stage {
+            self.last_played = Some((name, alt, form));
+            self.last_used_alts.insert(name, alt);
 
-                if self.current_alt.is_none() {
-                    error!("Unable to use alt {} for stage {:#x}", alt, name.0);
-                }
+            // With stage morph, every stage of the selection list is loaded for the same match
+            if self.selection.len() <= 1 {
+                self.deactivate_other_alts(name);
             }
         }
-    }
 
-    pub fn does_current_alt_have_folder(&self, folder: Hash40) -> bool {
-        let Some(alt) = self.current_alt.clone() else {
-            return false;
-        };
+        if alt == 0 {
+            info!("Since the alt id is 0, there will be no alt");
+            self.change_alt(name, None);
+            return;
+        }
 
-        info!("Loading folder {:#x}", folder.0);
+        self.change_alt(
+            name,
+            self.alt_infos
+                .get(&name)
+                .and_then(|info| info.alts_found.get(alt))
+                .cloned(),
+        );
 
-        alt.alt_folders.contains_key(&folder)
+        if !self.active_alts.contains_key(&name) {
+            error!("Unable to use alt {} for stage {}", alt, name.label());
+        }
+    }
+
+    /// Gets the active alt that replaces the folder, along with the name of its stage. The folders of an alt are all
+    /// inside of its stage's folder, so at most one of the active alts can have it.
+    pub fn get_active_alt_for_folder(&self, folder: Hash40) -> Option<(Hash40, Arc<StageAlt>)> {
+        info!("Loading folder {}", folder.label());
+
+        self.active_alts
+            .iter()
+            .find(|(_, alt)| alt.alt_folders.contains_key(&folder))
+            .map(|(stage_name, alt)| (*stage_name, alt.clone()))
     }
 
     pub fn get_sharing_base_for_alt_folder(
         &self,
         folder: Hash40,
     ) -> Option<&HashMap<Hash40, (u32, u32)>> {
-        self.current_alt.as_ref().map(|alt| &alt.sharing_base)
+        self.active_alts
+            .values()
+            .find(|alt| alt.alt_folders.contains_key(&folder))
+            .map(|alt| &alt.sharing_base)
     }
 
-    pub fn get_files_for_alt_folder(&self, folder: Hash40) -> Option<Vec<FilePathIdx>> {
-        let alt = self.current_alt.clone()?;
-
-        let base_folder = folder;
+    pub fn get_files_for_alt_folder(alt: &StageAlt, folder: Hash40) -> Option<Vec<FilePathIdx>> {
         let Some(folder) = alt.alt_folders.get(&folder).copied() else {
-            error!("Could not get the folder {:#x} for the current alt!", folder.0);
+            error!("Could not get the folder {} for the alt!", folder.label());
             return None;
         };
 
+        Self::get_files_in_folder(folder)
+    }
+
+    /// Collects the file path indices of every file directly inside of the provided folder, skipping any of our `.flag` files
+    pub fn get_files_in_folder(folder: Hash40) -> Option<Vec<FilePathIdx>> {
         let instance = FilesystemInfo::instance().unwrap();
         let search = instance.search();
-        let search_mut = FilesystemInfo::instance_mut().unwrap().search_mut();
         let arc = instance.arc();
 
         if search.get_folder_path_entry_from_hash(folder).is_err() {
             error!(
-                "Could not find the folder path entry for folder {:#x}",
-                folder.0
+                "Could not find the folder path entry for folder {}",
+                folder.label()
             );
             return None;
         }
@@ -569,7 +1452,7 @@ impl StageAltManager {
             .into_iter()
             .filter_map(|file| {
                 let SearchEntry::File(index) = file else {
-                    error!("Folder encountered in flattened children of {:#x}", folder.0);
+                    error!("Folder encountered in flattened children of {}", folder.label());
                     return None;
                 };
 
@@ -582,14 +1465,14 @@ impl StageAltManager {
                 match arc.get_file_path_index_from_hash(path.path.hash40()) {
                     Ok(index) => {
                         info!(
-                            "Retrieving file {:#x} with index {:#x}",
-                            path.path.hash40().0,
+                            "Retrieving file {} with index {:#x}",
+                            path.path.hash40().label(),
                             index.0
                         );
                         Some(index)
                     }
                     Err(_) => {
-                        error!("FilePathIdx for {:#x} was not found!", path.path.hash40().0);
+                        error!("FilePathIdx for {} was not found!", path.path.hash40().label());
                         None
                     }
                 }
@@ -599,13 +1482,173 @@ impl StageAltManager {
         Some(files)
     }
 
-    pub fn hack_lookups_for_alt(&self) {
-        let folder_lookup = if let Some(alt) = self.current_alt.as_ref() {
-            &alt.alt_folders
-        } else {
+    /// Requests the files of the alt for the provided form to be loaded ahead of time, replacing any preload that was
+    /// previously requested by the same stage preview.
+    ///
+    /// This is called as soon as an alt is registered on the stage select screen so that the heavy lifting of loading
+    /// the alt's files is done while the players are still picking, instead of when the directory is initialized.
+    pub fn request_preload(&mut self, preview_index: usize, stage_name: Hash40, alt: usize, form: StageForm) {
+        self.preload_requests.push(PreloadRequest::Queue {
+            preview_index,
+            stage_name,
+            alt,
+            form,
+        });
+    }
+
+    /// Requests the preload of the provided stage preview to be cancelled, if there is one
+    pub fn request_cancel_preload(&mut self, preview_index: usize) {
+        self.preload_requests.push(PreloadRequest::Cancel(preview_index));
+    }
+
+    /// Requests every preload to be released. This is done once we are back on the stage select screen (or the main
+    /// menu), since by that point the alts have either been loaded via their directories or were never used.
+    pub fn request_release_preloads(&mut self) {
+        self.preload_requests.push(PreloadRequest::ReleaseAll);
+    }
+
+    /// Handles the preload requests in the order they were made, returning the files that still need to be added to
+    /// the load lists. This must only be called from the resource loop.
+    pub fn handle_preload_requests(&mut self) -> Vec<u32> {
+        let mut queued = vec![];
+
+        for request in std::mem::take(&mut self.preload_requests) {
+            match request {
+                PreloadRequest::Queue {
+                    preview_index,
+                    stage_name,
+                    alt,
+                    form,
+                } => {
+                    self.preload(preview_index, stage_name, alt, form);
+                    if !queued.contains(&preview_index) {
+                        queued.push(preview_index);
+                    }
+                }
+                PreloadRequest::Cancel(preview_index) => self.cancel_preload(preview_index),
+                PreloadRequest::ReleaseAll => self.release_preloads(),
+            }
+        }
+
+        // A preload that was cancelled by a later request doesn't need to be loaded anymore
+        queued
+            .into_iter()
+            .filter_map(|preview_index| self.preloaded_files.get(&preview_index))
+            .flat_map(|preload| preload.folders.values().flatten().copied())
+            .collect()
+    }
+
+    /// Takes a reference to every file of the alt that is loaded for the form, replacing the preload of the stage preview
+    fn preload(&mut self, preview_index: usize, stage_name: Hash40, alt: usize, form: StageForm) {
+        self.cancel_preload(preview_index);
+
+        if alt == 0 {
+            info!("Alt 0 is the vanilla stage, there is nothing to preload");
+            return;
+        }
+
+        let Some(stage_alt) = self
+            .alt_infos
+            .get(&stage_name)
+            .and_then(|info| info.alts_found.get(alt))
+            .cloned() else {
+            error!("Unable to preload alt {} for stage {} because it does not exist", alt, stage_name.label());
+            return;
+        };
+
+        let info = FilesystemInfo::instance().unwrap();
+
+        let mut folders = HashMap::new();
+        let mut count = 0;
+        for (base, modded) in stage_alt.get_folders_for_form(form) {
+            let Some(folder_files) = Self::get_files_in_folder(modded) else {
+                continue;
+            };
+
+            let files: Vec<u32> = folder_files.into_iter().map(|file| file.0).collect();
+            for file in files.iter() {
+                unsafe {
+                    crate::refc(info, *file);
+                }
+            }
+
+            count += files.len();
+            folders.insert(base, files);
+        }
+
+        info!(
+            "Queued {} files for preloading alt {} of stage {} @ {}",
+            count,
+            alt,
+            stage_name.label(),
+            form
+        );
+
+        self.preloaded_files.insert(preview_index, Preload { stage_name, folders });
+    }
+
+    /// Cancels the preload that was requested by the provided stage preview, if there is one.
+    ///
+    /// The files might have already been added to the load lists, but dropping our references allows the resource
+    /// service to free them once they are no longer needed.
+    fn cancel_preload(&mut self, preview_index: usize) {
+        let Some(preload) = self.preloaded_files.remove(&preview_index) else {
             return;
         };
 
+        info!(
+            "Cancelling the preload of {} folders for preview {}",
+            preload.folders.len(),
+            preview_index
+        );
+
+        let info = FilesystemInfo::instance().unwrap();
+        for file in preload.folders.values().flatten() {
+            unsafe {
+                crate::unrefc(info, *file);
+            }
+        }
+    }
+
+    /// Releases every preload that is being held
+    fn release_preloads(&mut self) {
+        let previews: Vec<usize> = self.preloaded_files.keys().copied().collect();
+        for preview in previews {
+            self.cancel_preload(preview);
+        }
+    }
+
+    /// Releases the preloaded files of the folder once its directory has been swapped. The other folders of the
+    /// preload keep their references until their own directories are swapped.
+    pub fn release_preloaded_folder(&mut self, stage_name: Hash40, folder: Hash40) {
+        let info = FilesystemInfo::instance().unwrap();
+
+        self.preloaded_files.retain(|preview, preload| {
+            if preload.stage_name != stage_name {
+                return true;
+            }
+
+            if let Some(files) = preload.folders.remove(&folder) {
+                info!(
+                    "Releasing the preload of {} for preview {} since it has been loaded",
+                    folder.label(),
+                    preview
+                );
+
+                for file in files {
+                    unsafe {
+                        crate::unrefc(info, file);
+                    }
+                }
+            }
+
+            !preload.folders.is_empty()
+        });
+    }
+
+    pub fn hack_lookups_for_alt(&self, alt: &StageAlt) {
+        let folder_lookup = &alt.alt_folders;
+
         let mut file_lookup = HashMap::new();
 
         let info = FilesystemInfo::instance_mut().unwrap();
@@ -659,12 +1702,8 @@ impl StageAltManager {
         }
     }
 
-    pub fn unhack_lookups_for_alt(&self) {
-        let folder_lookup = if let Some(alt) = self.current_alt.as_ref() {
-            &alt.alt_folders
-        } else {
-            return;
-        };
+    pub fn unhack_lookups_for_alt(&self, alt: &StageAlt) {
+        let folder_lookup = &alt.alt_folders;
 
         let mut file_lookup = HashMap::new();
 
@@ -738,8 +1777,30 @@ pub static STAGE_ALT_MANAGER: Lazy<RwLock<StageAltManager>> = Lazy::new(|| {
         alts: vec![],
         selection: vec![],
         current_index: usize::MAX,
-        current_alt: None,
-        is_online: false,
+        active_alts: HashMap::new(),
+        game_mode: GameMode::LocalSmash,
+        preloaded_files: HashMap::new(),
+        preload_requests: vec![],
+        injection_policy: config::get().injection_policy(),
+        default_alts: HashMap::new(),
+        alt_orders: HashMap::new(),
+        profile: config::get()
+            .active_profile
+            .as_deref()
+            .and_then(SelectionProfile::named)
+            .unwrap_or_else(SelectionProfile::base),
+        random_disabled: random_toggles::load(),
+        series: None,
+        last_played: None,
+        last_used_alts: HashMap::new(),
+        result_stage_alts: HashMap::new(),
+        rules: config::get().rules.iter().map(AltRule::from_config).collect(),
+        spirit_alts: config::get()
+            .spirit_alts
+            .iter()
+            .map(|(spirit, alt)| (labels::hash(spirit), *alt))
+            .collect(),
+        current_spirit: None,
     })
 });
 
@@ -0,0 +1,406 @@
+use std::{collections::HashMap, path::Path, str::FromStr};
+
+use log::LevelFilter;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+
+use crate::{alts::LoadInjectionPolicy, logger::LoggerConfig, modes::GameMode};
+
+const CONFIG_PATH: &str = "sd:/ultimate/stage-alts/config.json";
+
+static CONFIG: OnceCell<Config> = OnceCell::new();
+
+/// The number of load lists in the resource service
+const LOAD_LIST_COUNT: usize = 5;
+
+/// How an alt is picked when the alt is chosen randomly
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RandomPolicy {
+    /// Any alt can be picked, including the vanilla stage
+    Any,
+
+    /// Only the alts can be picked, the vanilla stage is only used if there are no alts that can be picked
+    AltsOnly,
+
+    /// The vanilla stage is always used
+    Vanilla,
+}
+
+/// What the random panels of the stage select screen pick from
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RandomPool {
+    /// The game picks a stage, and then one of its alts is picked
+    Stage,
+
+    /// Every alt of every unlocked stage is its own entry, so stages with more alts are picked more often.
+    /// The game's own random stage settings are not read, so stages are left out of this pool by turning all of
+    /// their alts off for random instead.
+    Alt,
+}
+
+/// Which alt is used for the stage on the results screen
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResultStagePolicy {
+    /// A random alt of the result stage is used
+    Random,
+
+    /// The vanilla result stage is always used
+    Vanilla,
+
+    /// The alt of the result stage from `result-stage-alts` for the stage that was played is used
+    Match,
+
+    /// The provided alt of the result stage is always used, such as `{ "fixed": 2 }`
+    Fixed(usize),
+}
+
+/// How the alts are picked in a game mode
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ModePolicy {
+    /// The alts picked on the stage select screen are used
+    Selection,
+
+    /// The vanilla stage is always used
+    Vanilla,
+
+    /// A random alt is always used
+    Random,
+
+    /// The default alt of the stage is always used
+    Default,
+}
+
+/// A button that can be held while confirming a stage. ZL and ZR aren't allowed since they cycle through the alts of
+/// the stage on the stage select screen.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConfirmButton {
+    L,
+    R,
+    X,
+    Y,
+    Plus,
+    Minus,
+}
+
+/// The buttons that can be held while confirming a stage to override the alt that is shown in the preview. None of
+/// the overrides have a button by default.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case", default)]
+pub struct ConfirmOverrides {
+    /// Forces the vanilla stage
+    pub vanilla: Option<ConfirmButton>,
+
+    /// Forces a random alt of the stage
+    pub random: Option<ConfirmButton>,
+
+    /// Uses the alt of the stage that was loaded last
+    pub last_used: Option<ConfirmButton>,
+}
+
+/// The alt that a rule picks
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RuleAlt {
+    /// The provided alt, such as `{ "fixed": 2 }`
+    Fixed(usize),
+
+    /// A random alt
+    Random,
+
+    /// The vanilla stage
+    Vanilla,
+
+    /// The default alt of the stage
+    Default,
+
+    /// The alt from `spirit-alts` for the spirit that is being fought. The rule is skipped if there isn't one.
+    Spirit,
+}
+
+/// A rule for picking the alt when there is no selection from the stage select screen, such as in classic mode or
+/// spirit battles. Each of the conditions that are set must match for the rule to be used.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct RuleConfig {
+    /// The game mode that the rule applies to
+    #[serde(default)]
+    pub mode: Option<GameMode>,
+
+    /// The stage that the rule applies to, by name
+    #[serde(default)]
+    pub stage: Option<String>,
+
+    /// The spirit that the rule applies to, by its `ui_spirit_id`
+    #[serde(default)]
+    pub spirit: Option<String>,
+
+    /// The alt that the rule picks
+    pub alt: RuleAlt,
+}
+
+/// A named set of selection rules that can be switched to at runtime
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case", default)]
+pub struct ProfileConfig {
+    /// How alts are picked when they are chosen randomly, or the top-level policy if this is not set
+    pub random: Option<RandomPolicy>,
+
+    /// The tags used to filter the alts while this profile is active, these replace the top-level `active-tags`
+    pub tags: Vec<String>,
+
+    /// The alts that can be used for each stage, keyed by the stage name. Stages that are left out can use any alt.
+    /// The vanilla stage can always be used.
+    pub allowed_alts: HashMap<String, Vec<usize>>,
+
+    /// The weights used when randomly picking the alts of each stage, keyed by the stage name and indexed by alt.
+    /// Alts without a weight have a weight of 1, and alts with a weight of 0 are never randomly picked.
+    pub weights: HashMap<String, Vec<u32>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case", default)]
+pub struct LoggingConfig {
+    /// The maximum level of messages to log, one of `off`, `error`, `warn`, `info`, `debug` or `trace`
+    pub level: String,
+
+    /// The modules to log messages from, or every module if this is empty
+    pub modules: Vec<String>,
+
+    /// The size (in bytes) that the log file can reach before it gets rotated
+    pub max_size: u64,
+
+    /// The number of rotated log files to keep
+    pub max_files: usize,
+
+    /// Whether to also print the messages to stdout
+    pub console: bool,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        let logger = LoggerConfig::default();
+        Self {
+            level: logger.level.to_string().to_lowercase(),
+            modules: logger.modules,
+            max_size: logger.max_size,
+            max_files: logger.max_files,
+            console: logger.console,
+        }
+    }
+}
+
+/// The settings for injecting the files of loading directories into the load lists, see [`LoadInjectionPolicy`]
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case", default)]
+pub struct LoadInjectionConfig {
+    /// The mask checked against the flags of each child file, any file with one of these bits set is injected
+    pub flag_mask: u32,
+
+    /// The load list (0 to 4) to inject the files into, or the same list as their directory if this is not set
+    pub target_list: Option<usize>,
+
+    /// Whether to skip files that are already in the target list
+    pub dedupe: bool,
+
+    /// Whether to only log the files that would be injected without injecting them
+    pub dry_run: bool,
+}
+
+impl Default for LoadInjectionConfig {
+    fn default() -> Self {
+        let policy = LoadInjectionPolicy::default();
+        Self {
+            flag_mask: policy.flag_mask,
+            target_list: policy.target_list,
+            dedupe: policy.dedupe,
+            dry_run: policy.dry_run,
+        }
+    }
+}
+
+/// The mod-wide configuration, read from `sd:/ultimate/stage-alts/config.json`
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case", default)]
+pub struct Config {
+    /// How alts are picked when they are chosen randomly
+    pub random: RandomPolicy,
+
+    /// What the random panels of the stage select screen pick from
+    pub random_pool: RandomPool,
+
+    /// Whether alts can be used online. If this is disabled, the vanilla stage is always used online.
+    /// Even when this is enabled, only alts with a `wifi-safe.flag` are used.
+    pub allow_online: bool,
+
+    /// The default alt for each stage, keyed by the stage name (such as `battlefield`)
+    pub default_alts: HashMap<String, usize>,
+
+    /// The order that the alts of each stage are cycled through on the stage select screen, keyed by the stage name.
+    /// Any alts that are left out are cycled through after the listed ones, in folder order.
+    pub alt_order: HashMap<String, Vec<usize>>,
+
+    /// The names of the tags that alts can have. An alt has a tag when its folder contains a `tag-<name>.flag` file.
+    pub tags: Vec<String>,
+
+    /// The tags used to filter the alts. When this is not empty, only alts that have at least one of these tags
+    /// (and the vanilla stage) can be cycled to or randomly picked.
+    pub active_tags: Vec<String>,
+
+    /// The named selection profiles, which can be switched between on the stage select screen
+    pub profiles: HashMap<String, ProfileConfig>,
+
+    /// The profile that is active at startup, or none to use the top-level settings
+    pub active_profile: Option<String>,
+
+    pub logging: LoggingConfig,
+
+    pub load_injection: LoadInjectionConfig,
+
+    /// How the alts are picked in each game mode, such as `{ "training": "vanilla" }`. Modes that are left out use the
+    /// alts picked on the stage select screen.
+    pub mode_policies: HashMap<GameMode, ModePolicy>,
+
+    /// The rules for picking alts when there is no selection from the stage select screen, the first one that matches
+    /// is used. When none of them match, a random alt is used.
+    pub rules: Vec<RuleConfig>,
+
+    /// The alt to use for each spirit with the `spirit` rule alt, keyed by the spirit's `ui_spirit_id`
+    pub spirit_alts: HashMap<String, usize>,
+
+    /// The buttons that can be held while confirming a stage to override its alt
+    pub confirm_overrides: ConfirmOverrides,
+
+    /// Which alt is used for the stage on the results screen
+    pub result_stage: ResultStagePolicy,
+
+    /// The alt of the result stage to use for each played stage when the result stage policy is `match`. The keys are
+    /// either a stage name (such as `battlefield`) for every alt of the stage, or a stage name and alt (such as
+    /// `battlefield:2`) for a single alt. Stages that are left out use the vanilla result stage.
+    pub result_stage_alts: HashMap<String, usize>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            random: RandomPolicy::Any,
+            random_pool: RandomPool::Stage,
+            allow_online: true,
+            default_alts: HashMap::new(),
+            alt_order: HashMap::new(),
+            tags: vec![],
+            active_tags: vec![],
+            profiles: HashMap::new(),
+            active_profile: None,
+            logging: LoggingConfig::default(),
+            load_injection: LoadInjectionConfig::default(),
+            mode_policies: HashMap::new(),
+            rules: vec![],
+            confirm_overrides: ConfirmOverrides::default(),
+            spirit_alts: HashMap::new(),
+            result_stage: ResultStagePolicy::Random,
+            result_stage_alts: HashMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Checks the values that can't be checked while deserializing
+    fn validate(&self) -> Result<(), String> {
+        LevelFilter::from_str(&self.logging.level).map_err(|_| {
+            format!(
+                "logging.level: `{}` is not a valid level, expected one of `off`, `error`, `warn`, `info`, `debug` or `trace`",
+                self.logging.level
+            )
+        })?;
+
+        if let Some(tag) = self.active_tags.iter().find(|tag| !self.tags.contains(tag)) {
+            return Err(format!("active-tags: `{}` is not one of the tags", tag));
+        }
+
+        for (name, profile) in self.profiles.iter() {
+            if let Some(tag) = profile.tags.iter().find(|tag| !self.tags.contains(tag)) {
+                return Err(format!("profiles.{}.tags: `{}` is not one of the tags", name, tag));
+            }
+        }
+
+        if let Some(profile) = self.active_profile.as_ref() {
+            if !self.profiles.contains_key(profile) {
+                return Err(format!("active-profile: there is no profile named `{}`", profile));
+            }
+        }
+
+        if let Some(target_list) = self.load_injection.target_list {
+            if target_list >= LOAD_LIST_COUNT {
+                return Err(format!(
+                    "load-injection.target-list: {} is out of range, there are only {} load lists",
+                    target_list, LOAD_LIST_COUNT
+                ));
+            }
+        }
+
+        if self.logging.max_size == 0 {
+            return Err("logging.max-size: the log file size must be greater than 0".to_string());
+        }
+
+        Ok(())
+    }
+
+    pub fn injection_policy(&self) -> LoadInjectionPolicy {
+        LoadInjectionPolicy {
+            flag_mask: self.load_injection.flag_mask,
+            target_list: self.load_injection.target_list,
+            dedupe: self.load_injection.dedupe,
+            dry_run: self.load_injection.dry_run,
+        }
+    }
+
+    pub fn logger_config(&self) -> LoggerConfig {
+        LoggerConfig {
+            level: LevelFilter::from_str(&self.logging.level).unwrap_or(LevelFilter::Info),
+            modules: self.logging.modules.clone(),
+            max_size: self.logging.max_size,
+            max_files: self.logging.max_files,
+            console: self.logging.console,
+            ..LoggerConfig::default()
+        }
+    }
+}
+
+fn read_config() -> Result<Config, String> {
+    if !Path::new(CONFIG_PATH).exists() {
+        return Ok(Config::default());
+    }
+
+    let data = std::fs::read_to_string(CONFIG_PATH)
+        .map_err(|e| format!("Failed to read {}: {}", CONFIG_PATH, e))?;
+
+    let config: Config = serde_json::from_str(&data)
+        .map_err(|e| format!("Failed to parse {}: {}", CONFIG_PATH, e))?;
+
+    config
+        .validate()
+        .map_err(|e| format!("Invalid config {}: {}", CONFIG_PATH, e))?;
+
+    Ok(config)
+}
+
+/// Reads the config file. If the config is invalid, the default config is used and the error is returned
+/// so that it can be reported once the logger has been set up.
+pub fn load() -> Result<(), String> {
+    let (config, result) = match read_config() {
+        Ok(config) => (config, Ok(())),
+        Err(e) => (Config::default(), Err(e)),
+    };
+
+    let _ = CONFIG.set(config);
+    result
+}
+
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
@@ -0,0 +1,135 @@
+use std::{collections::BTreeMap, path::Path};
+
+use log::{error, info};
+use serde::Serialize;
+use smash_arc::Hash40;
+
+use crate::{
+    alts::{StageAlt, StageAltInfo, StageAltManager},
+    Hash40Ext,
+};
+
+const DUMP_PATH: &str = "sd:/ultimate/stage-alts/alt_dump.json";
+const DUMP_FLAG_PATH: &str = "sd:/ultimate/stage-alts/dump.flag";
+
+#[derive(Serialize)]
+struct AltFlagsDump {
+    is_normal_ws: bool,
+    is_normal_ignore: bool,
+    is_battle_ws: bool,
+    is_battle_ignore: bool,
+}
+
+#[derive(Serialize)]
+struct AltDump {
+    index: usize,
+    folders: BTreeMap<String, String>,
+    sharing_base: BTreeMap<String, (u32, u32)>,
+    ui_paths: Vec<String>,
+    flags: AltFlagsDump,
+    tags: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct StageDump {
+    stage_folder: String,
+    normal_folder: String,
+    battle_folder: String,
+    alts: Vec<AltDump>,
+}
+
+#[derive(Serialize)]
+struct RegistryDump {
+    version: &'static str,
+    stages: BTreeMap<String, StageDump>,
+}
+
+fn label(hash: Hash40) -> String {
+    hash.label().to_string()
+}
+
+impl AltDump {
+    fn new(index: usize, alt: &StageAlt) -> Self {
+        Self {
+            index,
+            folders: alt
+                .alt_folders
+                .iter()
+                .map(|(base, modded)| (label(*base), label(*modded)))
+                .collect(),
+            sharing_base: alt
+                .sharing_base
+                .iter()
+                .map(|(file, indices)| (label(*file), *indices))
+                .collect(),
+            ui_paths: alt.ui_paths.iter().copied().map(label).collect(),
+            flags: AltFlagsDump {
+                is_normal_ws: alt.is_normal_ws,
+                is_normal_ignore: alt.is_normal_ignore,
+                is_battle_ws: alt.is_battle_ws,
+                is_battle_ignore: alt.is_battle_ignore,
+            },
+            tags: {
+                let mut tags: Vec<String> = alt.tags.iter().cloned().collect();
+                tags.sort();
+                tags
+            },
+        }
+    }
+}
+
+impl StageDump {
+    fn new(info: &StageAltInfo) -> Self {
+        Self {
+            stage_folder: label(info.stage_folder),
+            normal_folder: label(info.normal_folder),
+            battle_folder: label(info.battle_folder),
+            alts: info
+                .alts_found
+                .iter()
+                .enumerate()
+                .map(|(index, alt)| AltDump::new(index, alt))
+                .collect(),
+        }
+    }
+}
+
+/// Serializes all of the discovered alts to JSON
+pub fn dump_registry(mgr: &StageAltManager) -> serde_json::Result<String> {
+    let dump = RegistryDump {
+        version: env!("CARGO_PKG_VERSION"),
+        stages: mgr
+            .alt_infos
+            .values()
+            .map(|info| (label(info.stage_name), StageDump::new(info)))
+            .collect(),
+    };
+
+    serde_json::to_string_pretty(&dump)
+}
+
+/// Writes the dump of all of the discovered alts to the SD card, returning whether it was successful
+pub fn write_dump(mgr: &StageAltManager) -> bool {
+    let json = match dump_registry(mgr) {
+        Ok(json) => json,
+        Err(e) => {
+            error!("Failed to serialize the alt registry: {}", e);
+            return false;
+        }
+    };
+
+    if let Err(e) = std::fs::write(DUMP_PATH, json) {
+        error!("Failed to write the alt registry dump to {}: {}", DUMP_PATH, e);
+        return false;
+    }
+
+    info!("Wrote the alt registry dump to {}", DUMP_PATH);
+    true
+}
+
+/// Writes the dump if the user has placed the dump flag on their SD card
+pub fn write_dump_if_requested(mgr: &StageAltManager) {
+    if Path::new(DUMP_FLAG_PATH).exists() {
+        write_dump(mgr);
+    }
+}
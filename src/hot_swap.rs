@@ -0,0 +1,108 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use log::info;
+use ninput::Buttons;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::{
+    alts::{self, CycleDirection},
+    modes::GameMode,
+};
+
+/// Whether the alts have been collected, the combo is ignored until then
+static READY: AtomicBool = AtomicBool::new(false);
+
+/// The hot swap that the polling thread has requested. The polling thread never touches the manager or the loaded
+/// directories itself, since they are also used by the resource thread, so the swap is done from the resource loop.
+static REQUEST: Lazy<Mutex<Option<CycleDirection>>> = Lazy::new(|| Mutex::new(None));
+
+const POLL_INTERVAL: Duration = Duration::from_millis(16);
+
+pub fn set_ready() {
+    READY.store(true, Ordering::SeqCst);
+}
+
+/// Gets the direction to cycle in if the combo has just been pressed. The combo is holding L and R and then pressing
+/// right on the d-pad to go to the next alt, or left to go to the previous one.
+fn poll_combo(was_down: &mut bool) -> Option<CycleDirection> {
+    let is_held = ninput::any::is_down(Buttons::L | Buttons::R);
+    let direction = if is_held && ninput::any::is_down(Buttons::RIGHT) {
+        Some(CycleDirection::Forward)
+    } else if is_held && ninput::any::is_down(Buttons::LEFT) {
+        Some(CycleDirection::Backward)
+    } else {
+        None
+    };
+
+    let is_down = direction.is_some();
+    let was_pressed = is_down && !*was_down;
+    *was_down = is_down;
+
+    if was_pressed {
+        direction
+    } else {
+        None
+    }
+}
+
+fn poll_loop() {
+    let mut was_down = false;
+
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+
+        if !READY.load(Ordering::SeqCst) {
+            continue;
+        }
+
+        if let Some(direction) = poll_combo(&mut was_down) {
+            info!("The hot swap combo was pressed, requesting to cycle the alt {:?}", direction);
+            *REQUEST.lock() = Some(direction);
+        }
+    }
+}
+
+/// Does the hot swap that was requested by the combo, if there is one, and reloads the stage with the new alt. This has
+/// to be called from the resource loop, so that the lookups and the loaded directories aren't changed while the
+/// resource thread is using them.
+pub fn handle_request() {
+    let Some(direction) = REQUEST.lock().take() else {
+        return;
+    };
+
+    let mut mgr = alts::get_mut();
+    if mgr.game_mode != GameMode::Training {
+        info!("Ignoring the hot swap request since we are not in training mode");
+        return;
+    }
+
+    let active_alt = |mgr: &alts::StageAltManager| {
+        mgr.last_played
+            .and_then(|(stage_name, ..)| mgr.active_alts.get(&stage_name).cloned())
+    };
+
+    let previous = active_alt(&mgr);
+    if !mgr.hot_swap_alt(direction) {
+        return;
+    }
+
+    let Some(current) = active_alt(&mgr) else {
+        return;
+    };
+
+    // The manager is unlocked first, since reloading the stage goes through the `init_loaded_dir` hook
+    drop(mgr);
+
+    unsafe {
+        crate::reload_stage(previous.as_deref(), &current);
+    }
+}
+
+/// Starts the thread that polls for the training mode hot swap combo
+pub fn install() {
+    std::thread::spawn(poll_loop);
+}
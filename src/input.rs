@@ -0,0 +1,55 @@
+use ninput::Buttons;
+
+use crate::config::{self, ConfirmButton};
+
+/// An override of the alt picked on the stage select screen, from a button held while confirming the stage
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConfirmOverride {
+    Vanilla,
+    Random,
+    LastUsed,
+}
+
+fn to_buttons(button: ConfirmButton) -> Buttons {
+    match button {
+        ConfirmButton::L => Buttons::L,
+        ConfirmButton::R => Buttons::R,
+        ConfirmButton::X => Buttons::X,
+        ConfirmButton::Y => Buttons::Y,
+        ConfirmButton::Plus => Buttons::PLUS,
+        ConfirmButton::Minus => Buttons::MINUS,
+    }
+}
+
+/// The npad ids of the controllers that can be used on the stage select screen: the eight players and handheld mode
+const CONTROLLER_IDS: [u32; 9] = [0, 1, 2, 3, 4, 5, 6, 7, 0x20];
+
+/// Gets the buttons held on the controller that is confirming the stage, which is the one holding A. The other
+/// players' controllers are ignored, so they can't override the alt of the player who is picking the stage.
+fn get_confirming_buttons() -> Option<Buttons> {
+    CONTROLLER_IDS
+        .iter()
+        .map(|&id| ninput::Controller::get_from_id(id).buttons)
+        .find(|buttons| buttons.contains(Buttons::A))
+}
+
+fn is_held(held: Buttons, button: Option<ConfirmButton>) -> bool {
+    button.map_or(false, |button| held.contains(to_buttons(button)))
+}
+
+/// Gets the override for the buttons that are held on the confirming controller, if any. When more than one override
+/// button is held, vanilla takes priority over random, which takes priority over the last used alt.
+pub fn get_confirm_override() -> Option<ConfirmOverride> {
+    let overrides = &config::get().confirm_overrides;
+    let held = get_confirming_buttons()?;
+
+    if is_held(held, overrides.vanilla) {
+        Some(ConfirmOverride::Vanilla)
+    } else if is_held(held, overrides.random) {
+        Some(ConfirmOverride::Random)
+    } else if is_held(held, overrides.last_used) {
+        Some(ConfirmOverride::LastUsed)
+    } else {
+        None
+    }
+}
@@ -1,47 +1,81 @@
 #![feature(let_else)]
 #![feature(label_break_value)]
-use std::{collections::HashMap, sync::atomic::Ordering};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::atomic::Ordering,
+};
 
-use containers::{LoadInfo, LoadType};
+use alts::StageForm;
+use modes::GameMode;
 use log::error;
 use once_cell::sync::Lazy;
 use rand::{thread_rng, RngCore};
 use skyline::hooks::InlineCtx;
-use smash_arc::{ArcLookup, FilePath, Hash40, PathListEntry, SearchLookup};
+use smash_arc::{ArcLookup, FilePath, FilePathIdx, Hash40, PathListEntry, SearchLookup};
+use stage_alts_core::{
+    containers::{self, LoadInfo, LoadType},
+    labels,
+};
 use types::{FilesystemInfo, LoadedDirectory, ResServiceNX};
 
 mod alts;
-mod containers;
-#[cfg(feature = "logger")]
+mod config;
+mod dump;
+mod hot_swap;
+mod input;
 mod logger;
 mod lua;
+mod modes;
+mod offsets;
+mod random_toggles;
 mod search;
 mod types;
 
 #[macro_export]
 macro_rules! hash40_fmt {
     ($str:expr $(, $args:expr)*) => {
-        Hash40::from(format!($str $(, $args)*).as_str())
+        $crate::labels::hash(format!($str $(, $args)*).as_str())
     }
 }
 
 trait Hash40Ext: Sized {
     fn concat<H: Into<Self>>(self, other: H) -> Self;
     fn join_path<H: Into<Self>>(self, other: H) -> Self;
+    /// Same as `concat`, but also registers the label of the result so that it shows up in the logs and the dump.
+    /// This should only be used for paths that are built once, since the labels are never removed.
+    fn concat_labeled<H: Into<Self>>(self, other: H) -> Self;
+    fn join_path_labeled<H: Into<Self>>(self, other: H) -> Self;
+    fn label(self) -> labels::Label;
 }
 
 impl Hash40Ext for Hash40 {
     fn concat<H: Into<Self>>(self, other: H) -> Self {
-        let raw = smash::phx::Hash40::new_raw(self.as_u64())
-            .concat(smash::phx::Hash40::new_raw(other.into().as_u64()))
-            .as_u64();
-
-        Self(raw)
+        let other = other.into();
+        Self(
+            smash::phx::Hash40::new_raw(self.as_u64())
+                .concat(smash::phx::Hash40::new_raw(other.as_u64()))
+                .as_u64(),
+        )
     }
 
     fn join_path<H: Into<Self>>(self, other: H) -> Self {
         self.concat("/").concat(other)
     }
+
+    fn concat_labeled<H: Into<Self>>(self, other: H) -> Self {
+        let other = other.into();
+        let result = self.concat(other);
+        labels::register_concat(self, other, result);
+        result
+    }
+
+    fn join_path_labeled<H: Into<Self>>(self, other: H) -> Self {
+        self.concat_labeled("/").concat_labeled(other)
+    }
+
+    fn label(self) -> labels::Label {
+        labels::Label(self)
+    }
 }
 
 extern "C" {
@@ -49,14 +83,23 @@ extern "C" {
     fn initial_loading(ctx: &InlineCtx);
 }
 
-#[skyline::from_offset(0x353fa20)]
-pub unsafe fn refc(table: &'static FilesystemInfo, index: u32);
+pub unsafe fn refc(table: &'static FilesystemInfo, index: u32) {
+    let func: extern "C" fn(&'static FilesystemInfo, u32) =
+        std::mem::transmute(offsets::text_addr(offsets::refc()));
+    func(table, index)
+}
 
-#[skyline::from_offset(0x353fb30)]
-pub unsafe fn unrefc(table: &'static FilesystemInfo, index: u32);
+pub unsafe fn unrefc(table: &'static FilesystemInfo, index: u32) {
+    let func: extern "C" fn(&'static FilesystemInfo, u32) =
+        std::mem::transmute(offsets::text_addr(offsets::unrefc()));
+    func(table, index)
+}
 
-#[skyline::from_offset(0x35455d0)]
-pub unsafe fn add_to_res_list(res_service: &'static ResServiceNX, index: u32, list_index: u32);
+pub unsafe fn add_to_res_list(res_service: &'static ResServiceNX, index: u32, list_index: u32) {
+    let func: extern "C" fn(&'static ResServiceNX, u32, u32) =
+        std::mem::transmute(offsets::text_addr(offsets::add_to_res_list()));
+    func(res_service, index, list_index)
+}
 
 static mut CURRENT_STAGE_INDEX: usize = 0;
 static mut INCOMING_RANDOM: usize = 0;
@@ -67,9 +110,12 @@ unsafe fn initial_loading_hook(ctx: &InlineCtx) {
 
     Lazy::force(&lua::UI_TO_HASH_LOOKUP);
     search::collect_alts();
+
+    dump::write_dump_if_requested(&alts::get());
+    hot_swap::set_ready();
 }
 
-#[skyline::hook(offset = 0x353fe30)]
+#[skyline::hook(offset = offsets::init_loaded_dir())]
 unsafe fn init_loaded_dir(info: &'static FilesystemInfo, index: u32) -> *mut LoadedDirectory {
     let result: *mut LoadedDirectory = call_original!(info, index);
 
@@ -77,31 +123,91 @@ unsafe fn init_loaded_dir(info: &'static FilesystemInfo, index: u32) -> *mut Loa
         return result;
     }
 
-    let loaded_directory = &mut *result;
+    let mgr = alts::get();
 
-    let Some(dir) = info.arc().get_dir_infos().get(index as usize) else { return result; };
+    let mut swapped = HashSet::new();
+    swap_loaded_directory(info, &mgr, index, &mut *result, &mut HashSet::new(), &mut swapped);
 
-    let mgr = alts::get();
+    drop(mgr);
 
-    if !mgr.does_current_alt_have_folder(dir.path.hash40()) {
-        log::info!("Alt does not have folder {:#x}", dir.path.hash40().0);
-        return result;
+    if !swapped.is_empty() {
+        let mut mgr = alts::get_mut();
+        for (stage_name, folder) in swapped {
+            mgr.release_preloaded_folder(stage_name, folder);
+        }
     }
 
-    log::info!("Current alt has folder {:#x}!", dir.path.hash40().0);
+    result
+}
 
-    let Some(files) = mgr.get_files_for_alt_folder(dir.path.hash40()) else {
-        error!("Current alt should have folder {:#x} but it was not found in the search section! Perhaps the config is incorrect?", dir.path.hash40().0);
-        return result;
-    };
+/// Replaces the files of the loaded directory with the files of the active alt of its stage, and then does the same for
+/// all of its child directories and the directory it redirects to.
+///
+/// Child directories are usually initialized (and therefore swapped) on their own before their parent is, so a
+/// directory whose files already match the alt is left alone to avoid taking extra references.
+///
+/// Every directory that now has its alt's files is added to `swapped`, along with the name of its stage.
+unsafe fn swap_loaded_directory(
+    info: &'static FilesystemInfo,
+    mgr: &alts::StageAltManager,
+    index: u32,
+    loaded_directory: &mut LoadedDirectory,
+    visited: &mut HashSet<*const LoadedDirectory>,
+    swapped: &mut HashSet<(Hash40, Hash40)>,
+) {
+    if !visited.insert(loaded_directory as *const LoadedDirectory) {
+        return;
+    }
+
+    let Some(dir) = info.arc().get_dir_infos().get(index as usize) else { return; };
+
+    'swap: {
+        let Some((stage_name, alt)) = mgr.get_active_alt_for_folder(dir.path.hash40()) else {
+            log::info!("No active alt has folder {}", dir.path.hash40().label());
+            break 'swap;
+        };
+
+        log::info!("Active alt has folder {}!", dir.path.hash40().label());
+
+        let Some(files) = alts::StageAltManager::get_files_for_alt_folder(&alt, dir.path.hash40()) else {
+            error!("Active alt should have folder {} but it was not found in the search section! Perhaps the config is incorrect?", dir.path.hash40().label());
+            break 'swap;
+        };
+
+        if loaded_directory
+            .child_path_indices
+            .iter()
+            .copied()
+            .eq(files.iter().map(|file| file.0))
+        {
+            log::info!("Folder {} has already been swapped", dir.path.hash40().label());
+            swapped.insert((stage_name, dir.path.hash40()));
+            break 'swap;
+        }
 
-    let arc = info.arc();
-    let file_paths = arc.get_file_paths();
+        replace_directory_files(info, loaded_directory, &files);
 
-    let sharing_base = mgr
-        .get_sharing_base_for_alt_folder(dir.path.hash40())
-        .unwrap();
+        swapped.insert((stage_name, dir.path.hash40()));
+    }
 
+    for child in loaded_directory.child_directories_mut() {
+        let child_index = child.file_group_index;
+        swap_loaded_directory(info, mgr, child_index, child, visited, swapped);
+    }
+
+    if let Some(redirection) = loaded_directory.redirection_mut() {
+        let redirection_index = redirection.file_group_index;
+        swap_loaded_directory(info, mgr, redirection_index, redirection, visited, swapped);
+    }
+}
+
+/// Replaces the files of the loaded directory, releasing the references to its old files and queueing the new ones
+/// for loading
+unsafe fn replace_directory_files(
+    info: &'static FilesystemInfo,
+    loaded_directory: &mut LoadedDirectory,
+    files: &[FilePathIdx],
+) {
     for child in loaded_directory.child_path_indices.iter() {
         unrefc(info, *child);
         // if info.get_loaded_datas()
@@ -121,24 +227,107 @@ unsafe fn init_loaded_dir(info: &'static FilesystemInfo, index: u32) -> *mut Loa
 
     loaded_directory.child_path_indices.clear();
 
-    for file in files {
+    for file in files.iter() {
         loaded_directory.child_path_indices.push(file.0);
         refc(info, file.0);
         add_to_res_list(ResServiceNX::instance().unwrap(), file.0, 0);
     }
+}
 
-    result
+/// Puts the vanilla files back into the loaded directories of the folders that only the previous alt replaced. The
+/// reload only swaps the folders of the active alts, so without this those directories would keep the previous alt's
+/// files. The folders that the new alt also has are left to the reload, since it swaps them straight to the new files.
+unsafe fn revert_loaded_directories(previous: &alts::StageAlt, current: &alts::StageAlt) {
+    let info = FilesystemInfo::instance().unwrap();
+
+    for dir in FilesystemInfo::instance_mut()
+        .unwrap()
+        .get_loaded_directories_mut()
+    {
+        if dir.ref_count.load(Ordering::SeqCst) == 0 {
+            continue;
+        }
+
+        let Some(path) = info.arc().get_dir_infos().get(dir.file_group_index as usize).map(|dir| dir.path.hash40()) else {
+            continue;
+        };
+
+        if !previous.alt_folders.contains_key(&path) || current.alt_folders.contains_key(&path) {
+            continue;
+        }
+
+        // The lookups of the previous alt have already been unpatched, so the folder has its vanilla files again
+        let Some(files) = alts::StageAltManager::get_files_in_folder(path) else {
+            error!("Unable to revert folder {} to its vanilla files", path.label());
+            continue;
+        };
+
+        log::info!("Reverting folder {} to its vanilla files", path.label());
+        replace_directory_files(info, dir, &files);
+    }
+}
+
+/// Reloads the loaded directories of the stage after its alt has been hot swapped, so that it is changed while the
+/// stage is loaded.
+///
+/// The folders that only the previous alt replaced are reverted to their vanilla files first. Every loaded directory of
+/// either alt is then initialized again through the game's `init_loaded_dir`, the same path a stage load takes, which
+/// swaps in the files of the new alt with our hook and queues them for loading. The reference that this takes is
+/// released right away with `uninit_loaded_dir`, since the stage already holds its own.
+///
+/// The manager must not be locked when calling this, since the `init_loaded_dir` hook locks it.
+pub unsafe fn reload_stage(previous: Option<&alts::StageAlt>, current: &alts::StageAlt) {
+    let info = FilesystemInfo::instance().unwrap();
+
+    if let Some(previous) = previous {
+        revert_loaded_directories(previous, current);
+    }
+
+    let indices: Vec<u32> = info
+        .get_loaded_directories()
+        .iter()
+        .filter(|dir| dir.ref_count.load(Ordering::SeqCst) != 0)
+        .map(|dir| dir.file_group_index)
+        .filter(|index| {
+            info.arc().get_dir_infos().get(*index as usize).map_or(false, |dir| {
+                let path = dir.path.hash40();
+                current.alt_folders.contains_key(&path)
+                    || previous.map_or(false, |previous| previous.alt_folders.contains_key(&path))
+            })
+        })
+        .collect();
+
+    let init: extern "C" fn(&'static FilesystemInfo, u32) -> *mut LoadedDirectory =
+        std::mem::transmute(offsets::text_addr(offsets::init_loaded_dir()));
+
+    for index in indices {
+        let dir = init(info, index);
+        if dir.is_null() {
+            error!("Unable to reload directory {}", index);
+            continue;
+        }
+
+        uninit_loaded_dir(info, dir);
+    }
 }
 
-#[skyline::hook(offset = 0x353e5c0)]
-unsafe fn uninit_loaded_dir(info: &'static FilesystemInfo, dir: *mut LoadedDirectory) {}
+pub unsafe fn uninit_loaded_dir(table: &'static FilesystemInfo, dir: *mut LoadedDirectory) {
+    let func: extern "C" fn(&'static FilesystemInfo, *mut LoadedDirectory) =
+        std::mem::transmute(offsets::text_addr(offsets::uninit_loaded_dir()));
+    func(table, dir)
+}
 
 #[skyline::hook(replace = res_loop_start)]
 unsafe fn res_loop_start_hook(ctx: &InlineCtx) {
+    hot_swap::handle_request();
+
     let info = FilesystemInfo::instance().unwrap();
     let Some(arc) = FilesystemInfo::instance().map(|i| i.arc()) else { return; };
     let Some(service) = ResServiceNX::instance_mut() else { return; };
 
+    let mut mgr = alts::get_mut();
+    let policy = mgr.injection_policy;
+
     let mut loads = [vec![], vec![], vec![], vec![], vec![]];
 
     for (list_idx, list) in service.res_lists.iter().enumerate() {
@@ -150,23 +339,51 @@ unsafe fn res_loop_start_hook(ctx: &InlineCtx) {
                 //     continue;
                 // };
 
+                let target_list = policy.target_list.unwrap_or(list_idx);
+                let Some(target) = loads.get_mut(target_list) else {
+                    error!("Load list {} is out of range, unable to inject files for directory {:#x}", target_list, entry.directory_index);
+                    continue;
+                };
+
                 for file in loaded_dir.child_path_indices.iter() {
                     let info_index = arc.get_file_info_indices()
                         [arc.get_file_paths()[*file as usize].path.index() as usize]
                         .file_info_index;
                     let info = &arc.get_file_infos()[info_index];
 
-                    if info.flags.unused4() & 5 != 0 {
-                        loads[list_idx].push(info.file_path_index.0);
+                    if info.flags.unused4() as u32 & policy.flag_mask != 0 {
+                        target.push(info.file_path_index.0);
                     }
                 }
             }
         }
     }
 
+    // Any alts that were selected on the stage select screen get preloaded here, since this is the only place where
+    // it is safe to take references to their files and modify the load lists
+    let preloaded = mgr.handle_preload_requests();
+    let preload_list = policy.target_list.unwrap_or(0);
+    match loads.get_mut(preload_list) {
+        Some(target) => target.extend(preloaded),
+        None => error!("Load list {} is out of range, unable to queue the preloaded files", preload_list),
+    }
+
+    drop(mgr);
+
     for (idx, load) in loads.into_iter().enumerate() {
+        let mut queued = HashSet::new();
         for load in load {
-            log::info!("Adding {:#x} to load list", load);
+            if policy.dedupe && (!queued.insert(load) || service.res_lists[idx].contains_file(load)) {
+                log::info!("Skipping {:#x} since it is already in load list {}", load, idx);
+                continue;
+            }
+
+            if policy.dry_run {
+                log::info!("[dry run] Would add {:#x} to load list {}", load, idx);
+                continue;
+            }
+
+            log::info!("Adding {:#x} to load list {}", load, idx);
             service.res_lists[idx].insert(LoadInfo {
                 ty: LoadType::File,
                 filepath_index: load,
@@ -179,7 +396,7 @@ unsafe fn res_loop_start_hook(ctx: &InlineCtx) {
     call_original!(ctx)
 }
 
-#[skyline::hook(offset = 0x25fd2b8, inline)]
+#[skyline::hook(offset = offsets::prepare_for_load(), inline)]
 unsafe fn prepare_for_load(ctx: &skyline::hooks::InlineCtx) {
     let search = FilesystemInfo::instance().unwrap().search();
     let Ok(path) = search.get_path_list_entry_from_hash(*ctx.registers[8].x.as_ref()) else {
@@ -188,50 +405,130 @@ unsafe fn prepare_for_load(ctx: &skyline::hooks::InlineCtx) {
     };
 
     let Ok(parent_path) = search.get_path_list_entry_from_hash(path.parent.hash40()) else {
-        error!("Failed to get the parent of the path {:#x}", path.path.hash40().0);
+        error!("Failed to get the parent of the path {}", path.path.hash40().label());
         return;
     };
 
     let mut mgr = alts::get_mut();
 
-    mgr.advance_alt(
-        parent_path.file_name.hash40(),
-        path.file_name.hash40() != Hash40::from("normal")
-            && parent_path.file_name.hash40() != Hash40::from("end"),
-    );
+    mgr.update_training_mode(smash::app::smashball::is_training_mode());
+
+    let form = if path.file_name.hash40() != Hash40::from("normal")
+        && parent_path.file_name.hash40() != Hash40::from("end")
+    {
+        StageForm::Battle
+    } else {
+        StageForm::Normal
+    };
+
+    mgr.advance_alt(parent_path.file_name.hash40(), form);
 }
 
-#[skyline::hook(offset = 0x22d91f0, inline)]
+#[skyline::hook(offset = offsets::online_melee_any_scene_create(), inline)]
 unsafe fn online_melee_any_scene_create(_: &InlineCtx) {
     let mut mgr = alts::get_mut();
-    mgr.is_online = true;
+    mgr.set_game_mode(GameMode::Quickplay);
 }
 
-#[skyline::hook(offset = 0x22d9120, inline)]
+#[skyline::hook(offset = offsets::bg_matchmaking_seq(), inline)]
 unsafe fn bg_matchmaking_seq(_: &InlineCtx) {
     let mut mgr = alts::get_mut();
-    mgr.is_online = true;
+    mgr.set_game_mode(GameMode::Quickplay);
 }
 
-#[skyline::hook(offset = 0x22d9050, inline)]
+#[skyline::hook(offset = offsets::arena_seq(), inline)]
 unsafe fn arena_seq(_: &InlineCtx) {
     let mut mgr = alts::get_mut();
-    mgr.is_online = true;
+    mgr.set_game_mode(GameMode::Arena);
+}
+
+#[skyline::hook(offset = offsets::elite_seq().expect("the mode hooks are only installed once their offsets have been found"), inline)]
+unsafe fn elite_seq(_: &InlineCtx) {
+    let mut mgr = alts::get_mut();
+    mgr.set_game_mode(GameMode::Elite);
 }
 
-#[skyline::hook(offset = 0x23599ac, inline)]
+#[skyline::hook(offset = offsets::classic_seq().expect("the mode hooks are only installed once their offsets have been found"), inline)]
+unsafe fn classic_seq(_: &InlineCtx) {
+    let mut mgr = alts::get_mut();
+    mgr.set_game_mode(GameMode::Classic);
+}
+
+#[skyline::hook(offset = offsets::spirits_seq().expect("the mode hooks are only installed once their offsets have been found"), inline)]
+unsafe fn spirits_seq(_: &InlineCtx) {
+    let mut mgr = alts::get_mut();
+    mgr.set_game_mode(GameMode::Spirits);
+}
+
+#[skyline::hook(offset = offsets::tourney_seq().expect("the mode hooks are only installed once their offsets have been found"), inline)]
+unsafe fn tourney_seq(_: &InlineCtx) {
+    let mut mgr = alts::get_mut();
+    mgr.set_game_mode(GameMode::Tourney);
+}
+
+#[skyline::hook(offset = offsets::spirit_battle_seq().expect("the mode hooks are only installed once their offsets have been found"), inline)]
+unsafe fn spirit_battle_seq(ctx: &InlineCtx) {
+    let mut mgr = alts::get_mut();
+    mgr.set_game_mode(GameMode::Spirits);
+    mgr.set_current_spirit(Hash40(*ctx.registers[0].x.as_ref()));
+}
+
+/// Installs the hooks for the modes whose offsets are only found through the signature file. The modes without an
+/// offset can't be detected, so they are played as the mode that their scene is reached through.
+fn install_mode_hooks() {
+    macro_rules! install_mode_hook {
+        ($hook:ident, $mode:expr, $fallback:expr) => {
+            if offsets::$hook().is_some() {
+                skyline::install_hook!($hook);
+            } else {
+                error!(
+                    "There is no signature for {}, so {} will be treated as {}",
+                    stringify!($hook),
+                    $mode,
+                    $fallback
+                );
+            }
+        };
+    }
+
+    install_mode_hook!(elite_seq, GameMode::Elite, GameMode::Quickplay);
+    install_mode_hook!(classic_seq, GameMode::Classic, GameMode::LocalSmash);
+    install_mode_hook!(spirits_seq, GameMode::Spirits, GameMode::LocalSmash);
+    install_mode_hook!(tourney_seq, GameMode::Tourney, GameMode::LocalSmash);
+
+    if offsets::spirit_battle_seq().is_some() {
+        skyline::install_hook!(spirit_battle_seq);
+    } else {
+        error!("There is no signature for spirit_battle_seq, so the spirit rules will never match");
+    }
+}
+
+#[skyline::hook(offset = offsets::main_menu(), inline)]
 unsafe fn main_menu(_: &InlineCtx) {
     let mut mgr = alts::get_mut();
     mgr.selection = vec![];
-    mgr.current_index = 0;
-    mgr.is_online = false;
+    mgr.reset_selection_index();
+    mgr.end_series();
+    mgr.clear_active_alts();
+    mgr.set_game_mode(GameMode::LocalSmash);
+    mgr.request_release_preloads();
 }
 
 #[skyline::main(name = "stage-alts")]
 pub fn main() {
-    #[cfg(feature = "logger")]
-    {
-        logger::init();
+    let config_result = config::load();
+
+    logger::init_with(config::get().logger_config());
+    logger::log_game_version(&offsets::game_version());
+
+    if let Err(e) = config_result {
+        error!("{}", e);
+    }
+    labels::init();
+
+    if offsets::OFFSETS.is_none() {
+        error!("The offsets could not be found, so stage-alts will not be loaded");
+        return;
     }
 
     skyline::install_hooks!(
@@ -244,6 +541,8 @@ pub fn main() {
         arena_seq,
         main_menu
     );
+    install_mode_hooks();
 
     lua::install();
+    hot_swap::install();
 }
@@ -1,36 +1,395 @@
-use log::Log;
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, Sender},
+};
+
+use log::{LevelFilter, Log};
+use once_cell::sync::OnceCell;
 use owo_colors::OwoColorize;
+use parking_lot::Mutex;
+
+const LOG_FILE_NAME: &str = "stage-alts.log";
+
+/// The configuration for the logger, this is provided once when the logger is initialized
+#[derive(Debug, Clone)]
+pub struct LoggerConfig {
+    /// The directory that the log files are written to
+    pub directory: PathBuf,
+
+    /// The maximum level of messages that will be logged
+    pub level: LevelFilter,
+
+    /// The modules (`search`, `alts`, `lua`, etc.) that messages will be logged from. If this is empty, then
+    /// messages from every module are logged
+    pub modules: Vec<String>,
+
+    /// The size (in bytes) that the log file can reach before it gets rotated
+    pub max_size: u64,
+
+    /// The number of rotated log files to keep around, in addition to the current one
+    pub max_files: usize,
+
+    /// Whether to also print the messages to stdout
+    pub console: bool,
+}
+
+impl Default for LoggerConfig {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("sd:/ultimate/stage-alts/logs"),
+            level: if cfg!(feature = "logger") {
+                LevelFilter::Info
+            } else {
+                LevelFilter::Warn
+            },
+            modules: vec![],
+            max_size: 1024 * 1024,
+            max_files: 3,
+            console: cfg!(feature = "logger"),
+        }
+    }
+}
+
+/// A message for the writer thread
+enum Message {
+    Line(String),
+    /// Flushes the log file, and then signals the sender once it has been flushed
+    Flush(Sender<()>),
+}
+
+/// Owns the log file on the writer thread, so that the SD card writes never block the threads that are logging
+struct LogWriter {
+    directory: PathBuf,
+    max_size: u64,
+    max_files: usize,
+    file: Option<BufWriter<File>>,
+    size: u64,
+}
+
+impl LogWriter {
+    fn new(config: &LoggerConfig) -> Self {
+        let mut writer = Self {
+            directory: config.directory.clone(),
+            max_size: config.max_size,
+            max_files: config.max_files,
+            file: None,
+            size: 0,
+        };
+
+        writer.file = writer.open();
+        writer.size = std::fs::metadata(writer.log_path(0))
+            .map(|metadata| metadata.len())
+            .unwrap_or(0);
+        writer
+    }
+
+    fn log_path(&self, index: usize) -> PathBuf {
+        if index == 0 {
+            self.directory.join(LOG_FILE_NAME)
+        } else {
+            self.directory.join(format!("{}.{}", LOG_FILE_NAME, index))
+        }
+    }
 
-struct Logger;
+    fn open(&self) -> Option<BufWriter<File>> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.log_path(0))
+            .ok()
+            .map(BufWriter::new)
+    }
+
+    /// Shifts each of the log files up by one, dropping the oldest one, and then starts a new log file
+    fn rotate(&mut self) {
+        self.flush();
+        self.file = None;
+
+        let _ = std::fs::remove_file(self.log_path(self.max_files));
+        for index in (0..self.max_files).rev() {
+            let _ = std::fs::rename(self.log_path(index), self.log_path(index + 1));
+        }
+
+        self.file = self.open();
+        self.size = 0;
+    }
+
+    fn write_line(&mut self, line: &str) {
+        let len = line.len() as u64 + 1;
+        if self.size + len > self.max_size {
+            self.rotate();
+        }
+
+        let Some(file) = self.file.as_mut() else {
+            return;
+        };
+
+        if writeln!(file, "{}", line).is_ok() {
+            self.size += len;
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some(file) = self.file.as_mut() {
+            let _ = file.flush();
+        }
+    }
+
+    fn handle(&mut self, message: Message) {
+        match message {
+            Message::Line(line) => self.write_line(&line),
+            Message::Flush(done) => {
+                self.flush();
+                let _ = done.send(());
+            }
+        }
+    }
+
+    /// Writes the lines as they come in, flushing once there is nothing left in the queue so that a burst of messages
+    /// only results in a single flush
+    fn run(mut self, receiver: Receiver<Message>) {
+        while let Ok(message) = receiver.recv() {
+            self.handle(message);
+            while let Ok(message) = receiver.try_recv() {
+                self.handle(message);
+            }
+            self.flush();
+        }
+    }
+}
+
+struct Logger {
+    config: LoggerConfig,
+    sender: Mutex<Sender<Message>>,
+}
+
+static LOGGER: OnceCell<Logger> = OnceCell::new();
+
+impl Logger {
+    /// Creates the logger along with the thread that writes to the log file
+    fn new(config: LoggerConfig) -> Self {
+        let writer = LogWriter::new(&config);
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || writer.run(receiver));
+
+        Self {
+            config,
+            sender: Mutex::new(sender),
+        }
+    }
+
+    /// Queues the line to be written by the writer thread
+    fn write_line(&self, line: &str) {
+        let _ = self.sender.lock().send(Message::Line(line.to_string()));
+    }
+
+    fn is_module_enabled(&self, module_path: Option<&str>) -> bool {
+        if self.config.modules.is_empty() {
+            return true;
+        }
+
+        let Some(module) = module_path.and_then(|path| path.split("::").nth(1)) else {
+            return false;
+        };
+
+        self.config.modules.iter().any(|enabled| enabled == module)
+    }
+}
 
 impl Log for Logger {
     fn enabled(&self, metadata: &log::Metadata) -> bool {
-        metadata.level() <= log::Level::Info
+        metadata.level() <= self.config.level
     }
 
-    fn flush(&self) {}
+    /// Waits for the writer thread to write everything that has been queued so far
+    fn flush(&self) {
+        let (done, wait) = mpsc::channel();
+        if self.sender.lock().send(Message::Flush(done)).is_ok() {
+            let _ = wait.recv();
+        }
+    }
 
     fn log(&self, record: &log::Record) {
-        if !self.enabled(record.metadata()) {
+        if !self.enabled(record.metadata()) || !self.is_module_enabled(record.module_path()) {
             return;
         }
 
         let message = format!(
             "[{}:{}] {}",
-            record.file().unwrap(),
-            record.line().unwrap(),
+            record.file().unwrap_or("unknown"),
+            record.line().unwrap_or(0),
             record.args()
         );
 
-        if record.level() == log::Level::Info {
-            println!("{}", message.green());
-        } else {
-            println!("{}", message.bright_red());
+        if self.config.console {
+            if record.level() == log::Level::Info {
+                println!("{}", message.green());
+            } else {
+                println!("{}", message.bright_red());
+            }
         }
+
+        self.write_line(&format!("[{}] {}", record.level(), message));
+    }
+}
+
+/// Writes a line to the log file regardless of the level and module filters
+fn write_header(line: &str) {
+    if let Some(logger) = LOGGER.get() {
+        logger.write_line(line);
     }
 }
 
-pub fn init() {
-    log::set_logger(&Logger).unwrap();
-    log::set_max_level(log::LevelFilter::Info);
+pub fn init_with(config: LoggerConfig) {
+    let _ = std::fs::create_dir_all(&config.directory);
+
+    let level = config.level;
+    let Ok(()) = LOGGER.set(Logger::new(config)) else {
+        return;
+    };
+
+    log::set_logger(LOGGER.get().unwrap()).unwrap();
+    log::set_max_level(level);
+
+    write_header(&format!(
+        "===== stage-alts v{} session started =====",
+        env!("CARGO_PKG_VERSION")
+    ));
+}
+
+/// Writes the game version to the session header
+pub fn log_game_version(version: &str) {
+    write_header(&format!("===== game version {} =====", version));
+}
+
+/// Writes the results of the alt discovery to the session header
+pub fn log_alt_count(stage_count: usize, alt_count: usize) {
+    write_header(&format!(
+        "===== discovered {} alts across {} stages =====",
+        alt_count, stage_count
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates an empty temporary directory for the log files of a test
+    fn temp_dir(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("stage-alts-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    fn test_config(directory: PathBuf) -> LoggerConfig {
+        LoggerConfig {
+            directory,
+            level: LevelFilter::Info,
+            modules: vec![],
+            max_size: 1024,
+            max_files: 2,
+            console: false,
+        }
+    }
+
+    fn read_log(directory: &std::path::Path, name: &str) -> Option<String> {
+        std::fs::read_to_string(directory.join(name)).ok()
+    }
+
+    fn log(logger: &Logger, level: log::Level, module_path: &str, message: &str) {
+        logger.log(
+            &log::Record::builder()
+                .level(level)
+                .module_path(Some(module_path))
+                .file(Some("src/test.rs"))
+                .line(Some(1))
+                .args(format_args!("{}", message))
+                .build(),
+        );
+    }
+
+    #[test]
+    fn rotates_log_files() {
+        let directory = temp_dir("rotation");
+        let mut writer = LogWriter::new(&LoggerConfig {
+            max_size: 10,
+            ..test_config(directory.clone())
+        });
+
+        for line in ["first", "second", "third", "fourth"] {
+            writer.write_line(line);
+        }
+        writer.flush();
+
+        assert_eq!(read_log(&directory, "stage-alts.log").as_deref(), Some("fourth\n"));
+        assert_eq!(read_log(&directory, "stage-alts.log.1").as_deref(), Some("third\n"));
+        assert_eq!(read_log(&directory, "stage-alts.log.2").as_deref(), Some("second\n"));
+        assert_eq!(read_log(&directory, "stage-alts.log.3"), None);
+
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn appends_to_the_existing_log_file() {
+        let directory = temp_dir("append");
+        std::fs::write(directory.join(LOG_FILE_NAME), "12345\n").unwrap();
+
+        let mut writer = LogWriter::new(&LoggerConfig {
+            max_size: 10,
+            ..test_config(directory.clone())
+        });
+        assert_eq!(writer.size, 6);
+
+        writer.write_line("more");
+        writer.flush();
+        assert_eq!(read_log(&directory, "stage-alts.log.1").as_deref(), Some("12345\n"));
+        assert_eq!(read_log(&directory, "stage-alts.log").as_deref(), Some("more\n"));
+
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn filters_by_level() {
+        let directory = temp_dir("level");
+        let logger = Logger::new(LoggerConfig {
+            level: LevelFilter::Warn,
+            ..test_config(directory.clone())
+        });
+
+        log(&logger, log::Level::Info, "stage_alts::alts", "info message");
+        log(&logger, log::Level::Warn, "stage_alts::alts", "warn message");
+        log(&logger, log::Level::Error, "stage_alts::alts", "error message");
+        logger.flush();
+
+        let contents = read_log(&directory, LOG_FILE_NAME).unwrap();
+        assert!(!contents.contains("info message"));
+        assert!(contents.contains("[WARN] [src/test.rs:1] warn message"));
+        assert!(contents.contains("[ERROR] [src/test.rs:1] error message"));
+
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn filters_by_module() {
+        let directory = temp_dir("module");
+        let logger = Logger::new(LoggerConfig {
+            modules: vec!["lua".to_string()],
+            ..test_config(directory.clone())
+        });
+
+        log(&logger, log::Level::Info, "stage_alts::lua", "lua message");
+        log(&logger, log::Level::Info, "stage_alts::search", "search message");
+        log(&logger, log::Level::Info, "stage_alts", "root message");
+        logger.flush();
+
+        let contents = read_log(&directory, LOG_FILE_NAME).unwrap();
+        assert!(contents.contains("lua message"));
+        assert!(!contents.contains("search message"));
+        assert!(!contents.contains("root message"));
+
+        let _ = std::fs::remove_dir_all(&directory);
+    }
 }
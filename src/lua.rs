@@ -9,8 +9,12 @@ use smash_arc::{ArcLookup, FilePathIdx, Hash40};
 use parking_lot::Mutex;
 
 use crate::{
-    alts::{self, Selection, STAGE_ALT_MANAGER},
+    alts::{self, Selection, StageForm, STAGE_ALT_MANAGER},
+    config::RandomPool,
+    input::{self, ConfirmOverride},
+    offsets,
     types::FilesystemInfo,
+    Hash40Ext,
 };
 
 pub static UI_TO_HASH_LOOKUP: Lazy<HashMap<Hash40, Hash40>> = Lazy::new(|| {
@@ -58,11 +62,24 @@ pub static UI_TO_HASH_LOOKUP: Lazy<HashMap<Hash40, Hash40>> = Lazy::new(|| {
 static PANEL_TO_HASH_LOOKUP2: Lazy<Mutex<HashMap<usize, Hash40>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
+/// The UI hashes of the random panels on the stage select screen
+static RANDOM_PANELS: Lazy<[Hash40; 4]> = Lazy::new(|| {
+    [
+        Hash40::from("ui_stage_random"),
+        Hash40::from("ui_stage_random_normal"),
+        Hash40::from("ui_stage_random_battle"),
+        Hash40::from("ui_stage_random_end"),
+    ]
+});
+
 // pub static mut INCOMING_ALT_NO: usize = 0;
 pub static mut INCOMING_ALTS: [usize; 3] = [0; 3];
 
 extern "C" fn register_alt(state: *mut lua::lua_State) -> i32 {
     unsafe {
+        let stage_form = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as i32;
+        lua::lua_pop(state, 1);
+
         let alt_no = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as usize;
         lua::lua_pop(state, 1);
 
@@ -84,35 +101,59 @@ extern "C" fn register_alt(state: *mut lua::lua_State) -> i32 {
 
         let mut mgr = alts::get_mut();
 
-        if [
-            Hash40::from("ui_stage_random"),
-            Hash40::from("ui_stage_random_normal"),
-            Hash40::from("ui_stage_random_battle"),
-            Hash40::from("ui_stage_random_end"),
-        ]
-        .contains(&ui_hash)
-        {
+        if RANDOM_PANELS.contains(&ui_hash) {
             info!(
                 "Setting stage selection for preview id {} to random!",
                 preview_id
             );
-            mgr.set_stage_selection(preview_id as usize, Selection::Random);
-        } else if let Some(stage_name) = UI_TO_HASH_LOOKUP.get(&ui_hash) {
+            mgr.set_stage_selection(
+                preview_id as usize,
+                Selection::Random {
+                    form: StageForm::from_random_panel(ui_hash),
+                },
+            );
+            mgr.request_cancel_preload(preview_id as usize);
+        } else if let Some(stage_name) = UI_TO_HASH_LOOKUP.get(&ui_hash).copied() {
+            // The held buttons are read here instead of in the stage select script, so that the override is applied
+            // to whatever stage is actually being registered
+            let alt_no = match input::get_confirm_override() {
+                Some(ConfirmOverride::Vanilla) => {
+                    info!("The vanilla override is held, using the vanilla stage for {}", stage_name.label());
+                    0
+                }
+                Some(ConfirmOverride::Random) => {
+                    info!(
+                        "The random override is held, setting stage selection for preview id {} to a random alt of {}!",
+                        preview_id, stage_name.label()
+                    );
+                    mgr.set_stage_selection(preview_id as usize, Selection::Random { form: None });
+                    mgr.request_cancel_preload(preview_id as usize);
+                    return 0;
+                }
+                Some(ConfirmOverride::LastUsed) => {
+                    let alt = mgr.get_last_used_alt(stage_name);
+                    info!("The last used override is held, using alt {} for {}", alt, stage_name.label());
+                    alt
+                }
+                None => alt_no,
+            };
+
             info!(
-                "Setting stage selection for preview id {} to {:#x} @ {}!",
-                preview_id, stage_name.0, alt_no
+                "Setting stage selection for preview id {} to {} @ {}!",
+                preview_id, stage_name.label(), alt_no
             );
             mgr.set_stage_selection(
                 preview_id as usize,
                 Selection::Regular {
-                    name: *stage_name,
+                    name: stage_name,
                     alt: alt_no,
                 },
             );
+            mgr.request_preload(preview_id as usize, stage_name, alt_no, to_stage_form(stage_form));
         } else {
             error!(
-                "Unable to get the stage name from the UI hash: {:#x}",
-                ui_hash.0
+                "Unable to get the stage name from the UI hash: {}",
+                ui_hash.label()
             );
         }
 
@@ -120,6 +161,14 @@ extern "C" fn register_alt(state: *mut lua::lua_State) -> i32 {
     }
 }
 
+/// Converts the `stage_form` from the stage select screen, falling back to the normal form if it is invalid
+fn to_stage_form(stage_form: i32) -> StageForm {
+    StageForm::from_index(stage_form).unwrap_or_else(|| {
+        error!("Stage form {} is invalid, using the normal form", stage_form);
+        StageForm::Normal
+    })
+}
+
 extern "C" fn get_next_alt(state: *mut lua::lua_State) -> i32 {
     unsafe {
         let stage_form = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as i32;
@@ -129,26 +178,15 @@ extern "C" fn get_next_alt(state: *mut lua::lua_State) -> i32 {
         let panel_id = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as i32;
         lua::lua_pop(state, 1);
 
-        let panel_id = panel_id as usize;
-
-        let panel_lookup = PANEL_TO_HASH_LOOKUP2.lock();
-
-        let Some(ui_hash) = panel_lookup.get(&panel_id).copied() else {
-            error!("Failed to get the UI hash from panel id {:#x}", panel_id);
-            lua::lua_pushinteger(state, 0);
-            return 1;
-        };
-
-        let Some(stage_hash) = UI_TO_HASH_LOOKUP.get(&ui_hash).copied() else {
-            error!("Failed to get the stage name from the UI hash {:#x}", ui_hash.0);
+        let Some(stage_hash) = get_stage_from_panel(panel_id as usize) else {
             lua::lua_pushinteger(state, 0);
             return 1;
         };
 
         let mgr = alts::get();
 
-        info!("Getting the next alt for {:#x} @ {}", stage_hash.0, alt_no);
-        let next = mgr.get_next_alt(stage_hash, alt_no as usize, stage_form != 0);
+        info!("Getting the next alt for {} @ {}", stage_hash.label(), alt_no);
+        let next = mgr.get_next_alt(stage_hash, alt_no as usize, to_stage_form(stage_form));
 
         lua::lua_pushinteger(state, next as i64);
 
@@ -165,28 +203,58 @@ extern "C" fn get_prev_alt(state: *mut lua::lua_State) -> i32 {
         let panel_id = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as i32;
         lua::lua_pop(state, 1);
 
-        let panel_id = panel_id as usize;
-
-        let panel_lookup = PANEL_TO_HASH_LOOKUP2.lock();
-
-        let Some(ui_hash) = panel_lookup.get(&panel_id).copied() else {
-            error!("Failed to get the UI hash from panel id {:#x}", panel_id);
+        let Some(stage_hash) = get_stage_from_panel(panel_id as usize) else {
             lua::lua_pushinteger(state, 0);
             return 1;
         };
 
-        let Some(stage_hash) = UI_TO_HASH_LOOKUP.get(&ui_hash).copied() else {
-            error!("Failed to get the stage name from the UI hash {:#x}", ui_hash.0);
+        let mgr = alts::get();
+
+        info!("Getting the prev alt for {} @ {}", stage_hash.label(), alt_no);
+        let next = mgr.get_prev_alt(stage_hash, alt_no as usize, to_stage_form(stage_form));
+
+        lua::lua_pushinteger(state, next as i64);
+
+        1
+    }
+}
+
+extern "C" fn resolve_alt_for_form(state: *mut lua::lua_State) -> i32 {
+    unsafe {
+        let stage_form = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as i32;
+        lua::lua_pop(state, 1);
+        let alt_no = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as usize;
+        lua::lua_pop(state, 1);
+        let panel_id = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as usize;
+        lua::lua_pop(state, 1);
+
+        let alt = get_stage_from_panel(panel_id).map_or(0, |stage_name| {
+            alts::get().resolve_alt_for_form(stage_name, alt_no, to_stage_form(stage_form))
+        });
+
+        lua::lua_pushinteger(state, alt as i64);
+        1
+    }
+}
+
+extern "C" fn get_default_alt(state: *mut lua::lua_State) -> i32 {
+    unsafe {
+        let stage_form = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as i32;
+        lua::lua_pop(state, 1);
+        let panel_id = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as i32;
+        lua::lua_pop(state, 1);
+
+        let Some(stage_hash) = get_stage_from_panel(panel_id as usize) else {
             lua::lua_pushinteger(state, 0);
             return 1;
         };
 
         let mgr = alts::get();
 
-        info!("Getting the prev alt for {:#x} @ {}", stage_hash.0, alt_no);
-        let next = mgr.get_prev_alt(stage_hash, alt_no as usize, stage_form != 0);
+        let default = mgr.get_usable_default_alt(stage_hash, to_stage_form(stage_form));
+        info!("Using default alt {} for {}", default, stage_hash.label());
 
-        lua::lua_pushinteger(state, next as i64);
+        lua::lua_pushinteger(state, default as i64);
 
         1
     }
@@ -219,7 +287,7 @@ extern "C" fn get_index_for_texture(state: *mut lua::lua_State) -> i32 {
         };
 
         let Some(stage_name) = UI_TO_HASH_LOOKUP.get(&ui_hash).copied() else {
-            error!("Failed to get stage name from UI hash {:#x}", ui_hash.0);
+            error!("Failed to get stage name from UI hash {}", ui_hash.label());
             lua::lua_pushinteger(state, default_index.0 as i64);
             return 1;
         };
@@ -243,15 +311,15 @@ extern "C" fn get_index_for_texture(state: *mut lua::lua_State) -> i32 {
         let path_index = arc.get_file_path_index_from_hash(path_hash).map_or_else(
             |_| {
                 error!(
-                    "Failed to get file path index from hash UI path hash {:#x}",
-                    path_hash.0
+                    "Failed to get file path index from hash UI path hash {}",
+                    path_hash.label()
                 );
                 default_index
             },
             |index| {
                 info!(
-                    "Using file path index {:#x} for {:#x} @ alt#{} + form#{}",
-                    index.0, stage_name.0, alt_no, stage_form
+                    "Using file path index {:#x} for {} @ alt#{} + form#{}",
+                    index.0, stage_name.label(), alt_no, stage_form
                 );
                 index
             },
@@ -278,7 +346,9 @@ extern "C" fn on_load(_state: *mut lua::lua_State) -> i32 {
         //     super::refc(info, *index);
         // }
         let mut mgr = alts::get_mut();
-        mgr.current_index = 0;
+        mgr.reset_selection_index();
+        mgr.clear_active_alts();
+        mgr.request_release_preloads();
         0
     }
 }
@@ -304,6 +374,252 @@ extern "C" fn set_stage_use_num(state: *mut lua::lua_State) -> i32 {
     }
 }
 
+extern "C" fn dump_alts(state: *mut lua::lua_State) -> i32 {
+    unsafe {
+        let success = crate::dump::write_dump(&alts::get());
+        lua::lua_pushboolean(state, success as i32);
+        1
+    }
+}
+
+/// Picks the stage for a random panel out of the pool of every alt, when the config uses that pool.
+///
+/// Returns the panel id and alt of the picked stage, which the stage select screen uses in place of the random panel
+/// so that the game loads the picked stage. Returns nothing if the panel is not a random panel, or if the game should
+/// pick the stage itself.
+extern "C" fn pick_random_stage(state: *mut lua::lua_State) -> i32 {
+    unsafe {
+        // The panels that can be picked, as a table of panel ids
+        let mut panels: Vec<usize> = vec![];
+        for index in 1..=lua::lua_rawlen(state, -1) {
+            lua::lua_rawgeti(state, -1, index as lua::lua_Integer);
+            panels.push(lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as usize);
+            lua::lua_pop(state, 1);
+        }
+        lua::lua_pop(state, 1);
+        let stage_form = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as i32;
+        lua::lua_pop(state, 1);
+        let panel_id = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as i32;
+        lua::lua_pop(state, 1);
+
+        if crate::config::get().random_pool != RandomPool::Alt {
+            return 0;
+        }
+
+        let mut stages: Vec<(usize, Hash40)> = vec![];
+        let form = {
+            let panel_lookup = PANEL_TO_HASH_LOOKUP2.lock();
+
+            let Some(ui_hash) = panel_lookup.get(&(panel_id as usize)).copied() else {
+                error!("Failed to get the UI hash from panel id {:#x}", panel_id);
+                return 0;
+            };
+
+            if !RANDOM_PANELS.contains(&ui_hash) {
+                return 0;
+            }
+
+            // The random panels themselves don't have a stage, so they are left out here
+            for panel_id in panels {
+                let Some(stage_name) = panel_lookup
+                    .get(&panel_id)
+                    .and_then(|ui_hash| UI_TO_HASH_LOOKUP.get(ui_hash))
+                    .copied()
+                else {
+                    continue;
+                };
+
+                if !stages.iter().any(|(_, name)| *name == stage_name) {
+                    stages.push((panel_id, stage_name));
+                }
+            }
+
+            // The form of the random panel takes priority over the form that the preview is showing
+            StageForm::from_random_panel(ui_hash).unwrap_or_else(|| to_stage_form(stage_form))
+        };
+
+        // The panel lookup is a hash map, so sort the stages to keep the pool in the same order every time
+        stages.sort_unstable_by_key(|(panel_id, _)| *panel_id);
+
+        let Some((panel_id, stage_name, alt)) = alts::get().get_random_pool_entry(&stages, form) else {
+            error!("There are no stages in the random pool, letting the game pick the stage");
+            return 0;
+        };
+
+        info!(
+            "Picked {} @ {} (panel {}) out of the random pool",
+            stage_name.label(), alt, panel_id
+        );
+
+        lua::lua_pushinteger(state, panel_id as i64);
+        lua::lua_pushinteger(state, alt as i64);
+        2
+    }
+}
+
+/// Gets the stage name for the panel id on the stage select screen
+fn get_stage_from_panel(panel_id: usize) -> Option<Hash40> {
+    let Some(ui_hash) = PANEL_TO_HASH_LOOKUP2.lock().get(&panel_id).copied() else {
+        error!("Failed to get the UI hash from panel id {:#x}", panel_id);
+        return None;
+    };
+
+    let Some(stage_name) = UI_TO_HASH_LOOKUP.get(&ui_hash).copied() else {
+        error!("Failed to get the stage name from the UI hash {}", ui_hash.label());
+        return None;
+    };
+
+    Some(stage_name)
+}
+
+extern "C" fn is_random_alt_enabled(state: *mut lua::lua_State) -> i32 {
+    unsafe {
+        let alt_no = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as usize;
+        lua::lua_pop(state, 1);
+        let panel_id = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as usize;
+        lua::lua_pop(state, 1);
+
+        let enabled = get_stage_from_panel(panel_id)
+            .map_or(true, |stage_name| alts::get().is_random_enabled(stage_name, alt_no));
+
+        lua::lua_pushboolean(state, enabled as i32);
+        1
+    }
+}
+
+extern "C" fn set_random_alt_enabled(state: *mut lua::lua_State) -> i32 {
+    unsafe {
+        let enabled = lua::lua_toboolean(state, -1) != 0;
+        lua::lua_pop(state, 1);
+        let alt_no = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as usize;
+        lua::lua_pop(state, 1);
+        let panel_id = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as usize;
+        lua::lua_pop(state, 1);
+
+        let success = get_stage_from_panel(panel_id).map_or(false, |stage_name| {
+            alts::get_mut().set_random_enabled(stage_name, alt_no, enabled)
+        });
+
+        lua::lua_pushboolean(state, success as i32);
+        1
+    }
+}
+
+/// Toggles random for the alt, returning whether random is now enabled for it and whether the toggle was saved
+extern "C" fn toggle_random_alt(state: *mut lua::lua_State) -> i32 {
+    unsafe {
+        let alt_no = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as usize;
+        lua::lua_pop(state, 1);
+        let panel_id = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as usize;
+        lua::lua_pop(state, 1);
+
+        let Some(stage_name) = get_stage_from_panel(panel_id) else {
+            error!("Failed to toggle random for alt {} of panel {:#x}", alt_no, panel_id);
+            lua::lua_pushboolean(state, 0);
+            lua::lua_pushboolean(state, 0);
+            return 2;
+        };
+
+        let mut mgr = alts::get_mut();
+        let enabled = !mgr.is_random_enabled(stage_name, alt_no);
+        let saved = mgr.set_random_enabled(stage_name, alt_no, enabled);
+        if !saved {
+            error!(
+                "Failed to save random being turned {} for {} @ {}, it will be reset on the next boot",
+                if enabled { "on" } else { "off" },
+                stage_name.label(),
+                alt_no
+            );
+        }
+
+        lua::lua_pushboolean(state, enabled as i32);
+        lua::lua_pushboolean(state, saved as i32);
+        2
+    }
+}
+
+extern "C" fn start_series(state: *mut lua::lua_State) -> i32 {
+    unsafe {
+        let game_count = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as usize;
+        lua::lua_pop(state, 1);
+        alts::get_mut().start_series(game_count);
+        0
+    }
+}
+
+extern "C" fn set_series_alt(state: *mut lua::lua_State) -> i32 {
+    unsafe {
+        let alt_no = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as usize;
+        lua::lua_pop(state, 1);
+        let panel_id = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as usize;
+        lua::lua_pop(state, 1);
+        let game = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as usize;
+        lua::lua_pop(state, 1);
+
+        let Some(stage_name) = get_stage_from_panel(panel_id) else {
+            lua::lua_pushboolean(state, 0);
+            return 1;
+        };
+
+        let success = alts::get_mut().set_series_selection(
+            game,
+            Selection::Regular {
+                name: stage_name,
+                alt: alt_no,
+            },
+        );
+
+        lua::lua_pushboolean(state, success as i32);
+        1
+    }
+}
+
+/// Returns the index of the next game of the series, or nothing if there is no series or it is over
+extern "C" fn get_series_game(state: *mut lua::lua_State) -> i32 {
+    unsafe {
+        match alts::get().series.as_ref().and_then(|series| series.next_game()) {
+            Some(game) => {
+                lua::lua_pushinteger(state, game as i64);
+                1
+            }
+            None => 0,
+        }
+    }
+}
+
+extern "C" fn end_series(_state: *mut lua::lua_State) -> i32 {
+    alts::get_mut().end_series();
+    0
+}
+
+extern "C" fn set_profile(state: *mut lua::lua_State) -> i32 {
+    unsafe {
+        let name = if lua::lua_isstring(state, -1) == 1 {
+            Some(skyline::from_c_str(lua::lua_tostring(state, -1) as _))
+        } else {
+            None
+        };
+        lua::lua_pop(state, 1);
+
+        let success = alts::get_mut().set_profile(name.as_deref());
+        lua::lua_pushboolean(state, success as i32);
+        1
+    }
+}
+
+extern "C" fn get_profile(state: *mut lua::lua_State) -> i32 {
+    unsafe {
+        match alts::get().profile.name.as_ref() {
+            Some(name) => {
+                let name = format!("{}\0", name);
+                lua::lua_pushstring(state, name.as_ptr() as _);
+            }
+            None => lua::lua_pushnil(state),
+        }
+        1
+    }
+}
+
 unsafe fn push_new_singleton(
     lua_state: *mut lua::lua_State,
     name: &'static str,
@@ -332,7 +648,7 @@ unsafe fn push_new_singleton(
     lua::bindings::auxsetstr(lua_state, value, real_name.as_ptr() as _);
 }
 
-#[skyline::hook(offset = 0x3373048, inline)]
+#[skyline::hook(offset = offsets::add_to_key_context(), inline)]
 unsafe fn add_to_key_context(ctx: &skyline::hooks::InlineCtx) {
     let lua_state: *mut lua::lua_State = *ctx.registers[19].x.as_ref() as _;
     let registry = &[
@@ -344,6 +660,14 @@ unsafe fn add_to_key_context(ctx: &skyline::hooks::InlineCtx) {
             name: "get_prev_alt\0".as_ptr() as _,
             func: Some(get_prev_alt),
         },
+        lua::luaL_Reg {
+            name: "resolve_alt_for_form\0".as_ptr() as _,
+            func: Some(resolve_alt_for_form),
+        },
+        lua::luaL_Reg {
+            name: "get_default_alt\0".as_ptr() as _,
+            func: Some(get_default_alt),
+        },
         lua::luaL_Reg {
             name: "send_message\0".as_ptr() as _,
             func: Some(send_message),
@@ -368,6 +692,50 @@ unsafe fn add_to_key_context(ctx: &skyline::hooks::InlineCtx) {
             name: "set_stage_use_num\0".as_ptr() as _,
             func: Some(set_stage_use_num),
         },
+        lua::luaL_Reg {
+            name: "dump_alts\0".as_ptr() as _,
+            func: Some(dump_alts),
+        },
+        lua::luaL_Reg {
+            name: "pick_random_stage\0".as_ptr() as _,
+            func: Some(pick_random_stage),
+        },
+        lua::luaL_Reg {
+            name: "is_random_alt_enabled\0".as_ptr() as _,
+            func: Some(is_random_alt_enabled),
+        },
+        lua::luaL_Reg {
+            name: "set_random_alt_enabled\0".as_ptr() as _,
+            func: Some(set_random_alt_enabled),
+        },
+        lua::luaL_Reg {
+            name: "toggle_random_alt\0".as_ptr() as _,
+            func: Some(toggle_random_alt),
+        },
+        lua::luaL_Reg {
+            name: "start_series\0".as_ptr() as _,
+            func: Some(start_series),
+        },
+        lua::luaL_Reg {
+            name: "set_series_alt\0".as_ptr() as _,
+            func: Some(set_series_alt),
+        },
+        lua::luaL_Reg {
+            name: "get_series_game\0".as_ptr() as _,
+            func: Some(get_series_game),
+        },
+        lua::luaL_Reg {
+            name: "end_series\0".as_ptr() as _,
+            func: Some(end_series),
+        },
+        lua::luaL_Reg {
+            name: "set_profile\0".as_ptr() as _,
+            func: Some(set_profile),
+        },
+        lua::luaL_Reg {
+            name: "get_profile\0".as_ptr() as _,
+            func: Some(get_profile),
+        },
         lua::luaL_Reg {
             name: std::ptr::null(),
             func: None,
@@ -376,7 +744,7 @@ unsafe fn add_to_key_context(ctx: &skyline::hooks::InlineCtx) {
     push_new_singleton(lua_state, "StageAltManager", registry);
 }
 
-#[skyline::hook(offset = 0x33590a0)]
+#[skyline::hook(offset = offsets::replace_texture())]
 unsafe fn replace_texture(state: *mut lua::lua_State) -> i32 {
     if dbg!(lua::lua_isinteger(state, -1)) == 1 {
         let index = lua::lua_tointegerx(state, -1, std::ptr::null_mut()) as i32;
@@ -395,7 +763,7 @@ struct StageEntry {
     params: [f32; 4],
 }
 
-#[skyline::hook(offset = 0x1b31ca0)]
+#[skyline::hook(offset = offsets::is_valid_entrance_param())]
 unsafe fn is_valid_entrance_param(arg: u64, arg2: i32) -> bool {
     let vec = &mut *((arg + 0x168) as *mut smash::cpp::Vector<StageEntry>);
 
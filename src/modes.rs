@@ -0,0 +1,40 @@
+use serde::Deserialize;
+
+/// The game mode that stages are being loaded for.
+///
+/// The modes are set by the scene hooks, and training is detected when a stage loads. The hooks for elite smash,
+/// classic, spirits and tourney are only installed when their offsets are found in the signature file. Without them,
+/// elite smash is treated as quickplay (since it is started through the same scene) and the others as local smash.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum GameMode {
+    LocalSmash,
+    Training,
+    Quickplay,
+    Arena,
+    Elite,
+    Classic,
+    Spirits,
+    Tourney,
+}
+
+impl GameMode {
+    pub fn is_online(self) -> bool {
+        matches!(self, Self::Quickplay | Self::Arena | Self::Elite)
+    }
+}
+
+impl std::fmt::Display for GameMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LocalSmash => write!(f, "local smash"),
+            Self::Training => write!(f, "training"),
+            Self::Quickplay => write!(f, "quickplay"),
+            Self::Arena => write!(f, "arena"),
+            Self::Elite => write!(f, "elite smash"),
+            Self::Classic => write!(f, "classic"),
+            Self::Spirits => write!(f, "spirits"),
+            Self::Tourney => write!(f, "tourney"),
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use std::{collections::HashMap, ffi::CStr, path::Path};
+
+use log::{error, info};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use skyline::hooks::{getRegionAddress, Region};
+use stage_alts_core::pattern::{find_pattern, parse_pattern, resolve_adrp};
+
+const SIGNATURES_PATH: &str = "sd:/ultimate/stage-alts/signatures.json";
+
+/// Declares the offset table along with a function to get each offset, so that they can be used
+/// in the hook attributes (`#[skyline::hook(offset = offsets::init_loaded_dir())]`)
+macro_rules! offsets {
+    ($($name:ident),* $(,)?) => {
+        /// The offsets (relative to the start of the text section) of every function and static that we use
+        #[derive(Debug, Copy, Clone)]
+        pub struct Offsets {
+            $(pub $name: usize,)*
+        }
+
+        impl Offsets {
+            const NAMES: &'static [&'static str] = &[$(stringify!($name),)*];
+
+            fn from_lookup(lookup: &HashMap<&'static str, usize>) -> Option<Self> {
+                Some(Self {
+                    $($name: *lookup.get(stringify!($name))?,)*
+                })
+            }
+        }
+
+        $(
+            pub fn $name() -> usize {
+                OFFSETS
+                    .as_ref()
+                    .expect("the offsets are only used once they have been found")
+                    .$name
+            }
+        )*
+    };
+}
+
+offsets!(
+    refc,
+    unrefc,
+    add_to_res_list,
+    init_loaded_dir,
+    uninit_loaded_dir,
+    prepare_for_load,
+    online_melee_any_scene_create,
+    bg_matchmaking_seq,
+    arena_seq,
+    main_menu,
+    add_to_key_context,
+    replace_texture,
+    is_valid_entrance_param,
+    fs_info,
+    res_service,
+);
+
+/// Declares the offsets of the optional hooks. These aren't in the version table, so they are only found through the
+/// signature file (on every game version), and the hooks are only installed for the offsets that were found.
+macro_rules! optional_offsets {
+    ($($name:ident),* $(,)?) => {
+        const OPTIONAL_NAMES: &[&str] = &[$(stringify!($name),)*];
+
+        $(
+            pub fn $name() -> Option<usize> {
+                OPTIONAL_OFFSETS.get(stringify!($name)).copied()
+            }
+        )*
+    };
+}
+
+optional_offsets!(
+    // These are inline hooks that are reached when each mode starts, after the scenes that set the online modes
+    elite_seq,
+    classic_seq,
+    spirits_seq,
+    tourney_seq,
+    // An inline hook at the start of a spirit battle, where x0 holds the `ui_spirit_id` of the spirit being fought
+    spirit_battle_seq,
+);
+
+/// The known offsets for each game version, keyed by the display version
+const VERSION_TABLE: &[(&str, Offsets)] = &[(
+    "13.0.1",
+    Offsets {
+        refc: 0x353fa20,
+        unrefc: 0x353fb30,
+        add_to_res_list: 0x35455d0,
+        init_loaded_dir: 0x353fe30,
+        uninit_loaded_dir: 0x353e5c0,
+        prepare_for_load: 0x25fd2b8,
+        online_melee_any_scene_create: 0x22d91f0,
+        bg_matchmaking_seq: 0x22d9120,
+        arena_seq: 0x22d9050,
+        main_menu: 0x23599ac,
+        add_to_key_context: 0x3373048,
+        replace_texture: 0x33590a0,
+        is_valid_entrance_param: 0x1b31ca0,
+        fs_info: 0x5330f20,
+        res_service: 0x5330f28,
+    },
+)];
+
+/// A byte signature used to find an offset when the game version is not in the version table
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Signature {
+    /// The bytes to search for, written as hex pairs separated by spaces. `??` matches any byte
+    pattern: String,
+
+    /// The distance from the start of the match to the offset
+    #[serde(default)]
+    offset: isize,
+
+    /// Whether the offset points to an `adrp` instruction followed by an `add`/`ldr`, in which case
+    /// the address that the instruction pair loads is used instead. This is how the statics are found.
+    #[serde(default)]
+    adrp: bool,
+}
+
+fn text_section() -> &'static [u8] {
+    unsafe {
+        let start = getRegionAddress(Region::Text) as *const u8;
+        let end = getRegionAddress(Region::Rodata) as *const u8;
+        std::slice::from_raw_parts(start, end.offset_from(start) as usize)
+    }
+}
+
+pub fn game_version() -> String {
+    unsafe {
+        let mut version = skyline::nn::oe::DisplayVersion { name: [0; 16] };
+        skyline::nn::oe::GetDisplayVersion(&mut version);
+        CStr::from_ptr(version.name.as_ptr() as _)
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+fn scan_signatures(signatures: &HashMap<String, Signature>, names: &[&'static str]) -> HashMap<&'static str, usize> {
+    let text = text_section();
+    let mut lookup = HashMap::new();
+
+    for name in names {
+        let Some(signature) = signatures.get(*name) else {
+            error!("There is no signature for {}", name);
+            continue;
+        };
+
+        let Some(pattern) = parse_pattern(&signature.pattern) else {
+            error!("The signature for {} is not a valid byte pattern", name);
+            continue;
+        };
+
+        let Some(position) = find_pattern(text, &pattern) else {
+            error!("The signature for {} was not found in the text section", name);
+            continue;
+        };
+
+        let offset = (position as isize + signature.offset) as usize;
+
+        let offset = if signature.adrp {
+            let read = |offset: usize| {
+                text.get(offset..offset + 4)
+                    .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+            };
+
+            let Some(address) = read(offset).zip(read(offset + 4)).and_then(|(adrp, next)| resolve_adrp(offset, adrp, next)) else {
+                error!("The signature for {} did not point to an adrp instruction pair", name);
+                continue;
+            };
+
+            address
+        } else {
+            offset
+        };
+
+        info!("Found {} at {:#x}", name, offset);
+        lookup.insert(*name, offset);
+    }
+
+    lookup
+}
+
+/// Reads the signature file, or returns `None` if there isn't one
+fn read_signatures() -> Result<Option<HashMap<String, Signature>>, String> {
+    if !Path::new(SIGNATURES_PATH).exists() {
+        return Ok(None);
+    }
+
+    std::fs::read_to_string(SIGNATURES_PATH)
+        .map_err(|e| e.to_string())
+        .and_then(|data| serde_json::from_str(&data).map_err(|e| e.to_string()))
+        .map(Some)
+        .map_err(|e| format!("Failed to read the signature file {}: {}", SIGNATURES_PATH, e))
+}
+
+fn find_offsets(version: &str) -> Result<Offsets, String> {
+    if let Some((_, offsets)) = VERSION_TABLE.iter().find(|(known, _)| *known == version) {
+        info!("Using the offset table for game version {}", version);
+        return Ok(*offsets);
+    }
+
+    info!(
+        "Game version {} is not in the offset table, falling back to signature scanning",
+        version
+    );
+
+    let Some(signatures) = read_signatures()? else {
+        return Err(format!(
+            "stage-alts does not support game version {} and there is no signature file at {}",
+            version, SIGNATURES_PATH
+        ));
+    };
+
+    let lookup = scan_signatures(&signatures, Offsets::NAMES);
+
+    Offsets::from_lookup(&lookup).ok_or_else(|| {
+        let missing: Vec<_> = Offsets::NAMES
+            .iter()
+            .filter(|name| !lookup.contains_key(*name))
+            .collect();
+        format!(
+            "stage-alts was unable to find the offsets for {:?} on game version {}",
+            missing, version
+        )
+    })
+}
+
+/// The offsets for the running game version, or `None` if they couldn't be found. Panicking here would take the whole
+/// game down, so the error is logged instead and the hooks are not installed.
+pub static OFFSETS: Lazy<Option<Offsets>> = Lazy::new(|| match find_offsets(&game_version()) {
+    Ok(offsets) => Some(offsets),
+    Err(e) => {
+        error!("{}", e);
+        None
+    }
+});
+
+/// The offsets of the optional hooks that were found in the signature file, see [`optional_offsets`]
+static OPTIONAL_OFFSETS: Lazy<HashMap<&'static str, usize>> = Lazy::new(|| match read_signatures() {
+    Ok(Some(signatures)) => scan_signatures(&signatures, OPTIONAL_NAMES),
+    Ok(None) => HashMap::new(),
+    Err(e) => {
+        error!("{}", e);
+        HashMap::new()
+    }
+});
+
+/// Gets the runtime address of the provided offset
+pub fn text_addr(offset: usize) -> usize {
+    unsafe { getRegionAddress(Region::Text) as usize + offset }
+}
@@ -0,0 +1,83 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::Path,
+};
+
+use log::{error, info};
+use smash_arc::Hash40;
+
+use crate::{labels, Hash40Ext};
+
+const TOGGLES_PATH: &str = "sd:/ultimate/stage-alts/random_toggles.json";
+
+/// Parses a stage name from the toggle file, which is either the name itself or the raw hash when the name is unknown
+fn parse_stage_name(name: &str) -> Option<Hash40> {
+    match name.strip_prefix("0x") {
+        Some(hash) => u64::from_str_radix(hash, 16).ok().map(Hash40),
+        None => Some(labels::hash(name)),
+    }
+}
+
+/// Loads the alts that have been disabled for random from the SD card, keyed by stage
+pub fn load() -> HashMap<Hash40, HashSet<usize>> {
+    if !Path::new(TOGGLES_PATH).exists() {
+        return HashMap::new();
+    }
+
+    let data = match std::fs::read_to_string(TOGGLES_PATH) {
+        Ok(data) => data,
+        Err(e) => {
+            error!("Failed to read the random toggles from {}: {}", TOGGLES_PATH, e);
+            return HashMap::new();
+        }
+    };
+
+    let toggles: BTreeMap<String, Vec<usize>> = match serde_json::from_str(&data) {
+        Ok(toggles) => toggles,
+        Err(e) => {
+            error!("Failed to parse the random toggles from {}: {}", TOGGLES_PATH, e);
+            return HashMap::new();
+        }
+    };
+
+    let mut disabled = HashMap::new();
+    for (stage_name, alts) in toggles {
+        let Some(stage_hash) = parse_stage_name(&stage_name) else {
+            error!("Invalid stage name in the random toggles: {}", stage_name);
+            continue;
+        };
+
+        disabled.insert(stage_hash, alts.into_iter().collect::<HashSet<usize>>());
+    }
+
+    info!("Loaded the random toggles for {} stages", disabled.len());
+    disabled
+}
+
+/// Writes the alts that have been disabled for random to the SD card, returning whether it was successful
+pub fn save(disabled: &HashMap<Hash40, HashSet<usize>>) -> bool {
+    let toggles: BTreeMap<String, Vec<usize>> = disabled
+        .iter()
+        .filter(|(_, alts)| !alts.is_empty())
+        .map(|(stage_name, alts)| {
+            let mut alts: Vec<usize> = alts.iter().copied().collect();
+            alts.sort_unstable();
+            (stage_name.label().to_string(), alts)
+        })
+        .collect();
+
+    let json = match serde_json::to_string_pretty(&toggles) {
+        Ok(json) => json,
+        Err(e) => {
+            error!("Failed to serialize the random toggles: {}", e);
+            return false;
+        }
+    };
+
+    if let Err(e) = std::fs::write(TOGGLES_PATH, json) {
+        error!("Failed to write the random toggles to {}: {}", TOGGLES_PATH, e);
+        return false;
+    }
+
+    true
+}
@@ -1,12 +1,12 @@
-use log::error;
+use log::{error, info};
 use smash_arc::{
     serde::Hash40String, ArcFile, ArcLookup, FolderPathListEntry, Hash40, HashToIndex, LoadedArc,
     LoadedSearchSection, LookupError, PathListEntry, SearchLookup,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
-    alts::{StageAlt, StageAltInfo},
+    alts::{StageAlt, StageAltInfo, StageAltManager},
     hash40_fmt,
     types::FilesystemInfo,
     Hash40Ext,
@@ -415,7 +415,7 @@ fn collect_folders(search: &LoadedSearchSection, path: Hash40, mut base: Hash40)
     let children = walk_search_section(search, path, 1);
 
     if base != Hash40(0) {
-        base = base.concat("/");
+        base = base.concat_labeled("/");
     }
 
     let mut out = vec![];
@@ -425,7 +425,7 @@ fn collect_folders(search: &LoadedSearchSection, path: Hash40, mut base: Hash40)
         };
 
         let path = search.get_path_list()[index as usize];
-        let next_path = base.concat(path.file_name.hash40());
+        let next_path = base.concat_labeled(path.file_name.hash40());
         out.push(next_path);
 
         out.extend(collect_folders(search, path.path.hash40(), next_path));
@@ -457,33 +457,45 @@ fn get_ui_files(stage_name: Hash40, alt_no: usize) -> [Hash40; 5] {
 
     [
         replace_path
-            .join_path("stage_0")
-            .join_path("stage_0_")
-            .concat(stage_name)
-            .concat(suffix),
+            .join_path_labeled("stage_0")
+            .join_path_labeled("stage_0_")
+            .concat_labeled(stage_name)
+            .concat_labeled(suffix),
         replace_path
-            .join_path("stage_1")
-            .join_path("stage_1_")
-            .concat(stage_name)
-            .concat(suffix),
+            .join_path_labeled("stage_1")
+            .join_path_labeled("stage_1_")
+            .concat_labeled(stage_name)
+            .concat_labeled(suffix),
         replace_path
-            .join_path("stage_2")
-            .join_path("stage_2_")
-            .concat(stage_name)
-            .concat(suffix),
+            .join_path_labeled("stage_2")
+            .join_path_labeled("stage_2_")
+            .concat_labeled(stage_name)
+            .concat_labeled(suffix),
         replace_path
-            .join_path("stage_3")
-            .join_path("stage_3_")
-            .concat(stage_name)
-            .concat(suffix),
+            .join_path_labeled("stage_3")
+            .join_path_labeled("stage_3_")
+            .concat_labeled(stage_name)
+            .concat_labeled(suffix),
         replace_path
-            .join_path("stage_4")
-            .join_path("stage_4_")
-            .concat(stage_name)
-            .concat(suffix),
+            .join_path_labeled("stage_4")
+            .join_path_labeled("stage_4_")
+            .concat_labeled(stage_name)
+            .concat_labeled(suffix),
     ]
 }
 
+/// Collects the names of the tags (from the config) that have a `tag-<name>.flag` file in the alt folder
+fn collect_tags(search: &LoadedSearchSection, alt_folder_index: u32) -> HashSet<String> {
+    crate::config::get()
+        .tags
+        .iter()
+        .filter(|tag| {
+            get_direct_child(search, alt_folder_index, hash40_fmt!("tag-{}.flag", tag)).is_some()
+        })
+        .cloned()
+        .collect()
+}
+
 fn collect_sharing_base(
     arc: &LoadedArc,
     search: &LoadedSearchSection,
@@ -515,7 +527,7 @@ fn collect_sharing_base(
             let file_paths = arc.get_file_paths();
             if file_paths[base_fp_index].path.index() != file_paths[modded_fp_index].path.index() {
                 out.insert(
-                    base_path,
+                    base.join_path_labeled(name),
                     (
                         file_paths[base_fp_index].path.index(),
                         file_paths[modded_fp_index].path.index(),
@@ -560,7 +572,7 @@ pub fn collect_alts() {
 
         // We attempt to get the normal path. This one is unconditional because every stage must have a normal folder, even battlefield
         let Some(normal_path) = get_direct_child(search, stage_folder_index, Hash40::from("normal")).map(|index| search.get_path_list()[index as usize]) else {
-            error!("Stage {:#x} did not have normal folder!", stage_path.file_name.hash40().0);
+            error!("Stage {} did not have normal folder!", stage_path.file_name.hash40().label());
             continue;
         };
 
@@ -585,6 +597,8 @@ pub fn collect_alts() {
                 get_direct_child(search, normal_alt_index, Hash40::from("wifi-ignore.flag"))
                     .is_some();
 
+            let mut tags = collect_tags(search, normal_alt_index);
+
             // When we get here, we know that we have an alt. So we are going to first collect the UI paths and the effect folder. Makes the most sense to do these
             // as they don't require any discovery -- they are static paths.
 
@@ -592,10 +606,10 @@ pub fn collect_alts() {
             // that it's going to be handled, but even if that isn't the case it enables not colliding with one-slot effects.
             let mut folder_lookup = HashMap::new();
             folder_lookup.insert(
-                Hash40::from("effect/stage").join_path(stage_path.file_name.hash40()),
+                Hash40::from("effect/stage").join_path_labeled(stage_path.file_name.hash40()),
                 Hash40::from("effect/stage")
-                    .join_path(stage_path.file_name.hash40())
-                    .concat(hash40_fmt!("_s{:02}", x)),
+                    .join_path_labeled(stage_path.file_name.hash40())
+                    .concat_labeled(hash40_fmt!("_s{:02}", x)),
             );
 
             // The UI files are also static and can just be generated.
@@ -621,23 +635,24 @@ pub fn collect_alts() {
             let folders = collect_folders(search, normal_path.path.hash40(), Hash40::from(""));
 
             // We create our folder lookup here, this is going to become part of the stage alt.
+            let mut normal_folders = HashSet::new();
             for folder in folders {
-                folder_lookup.insert(
-                    normal_path.path.hash40().join_path(folder),
-                    normal_alt.path.hash40().join_path(folder),
-                );
+                let base = normal_path.path.hash40().join_path_labeled(folder);
+                folder_lookup.insert(base, normal_alt.path.hash40().join_path_labeled(folder));
+                normal_folders.insert(base);
             }
 
             // do the same thing for the battle paths if they exist
             let mut is_battle_ws = false;
             let mut is_battle_ignore = false;
+            let mut battle_folders = HashSet::new();
             'battle: {
                 let Some(battle_path) = battle_path else {
                     break 'battle;
                 };
 
                 let Some(battle_alt_index) = get_direct_child(search, stage_folder_index, hash40_fmt!("battle_s{:02}", x)) else {
-                    error!("The battlefield form alt for {:#x} was not discovered even thought it is erquired.", stage_path.file_name.hash40().0);
+                    error!("The battlefield form alt for {} was not discovered even thought it is erquired.", stage_path.file_name.hash40().label());
                     break 'battle;
                 };
 
@@ -650,6 +665,8 @@ pub fn collect_alts() {
                     get_direct_child(search, battle_alt_index, Hash40::from("wifi-ignore.flag"))
                         .is_some();
 
+                tags.extend(collect_tags(search, battle_alt_index));
+
                 file_order_fix(
                     search_mut,
                     battle_path.path.hash40(),
@@ -659,10 +676,9 @@ pub fn collect_alts() {
                 let folders = collect_folders(search, battle_path.path.hash40(), Hash40::from(""));
 
                 for folder in folders {
-                    folder_lookup.insert(
-                        battle_path.path.hash40().join_path(folder),
-                        battle_alt.path.hash40().join_path(folder),
-                    );
+                    let base = battle_path.path.hash40().join_path_labeled(folder);
+                    folder_lookup.insert(base, battle_alt.path.hash40().join_path_labeled(folder));
+                    battle_folders.insert(base);
                 }
             }
 
@@ -670,64 +686,71 @@ pub fn collect_alts() {
 
             alts.push(std::sync::Arc::new(StageAlt {
                 alt_folders: folder_lookup,
+                normal_folders,
+                battle_folders,
                 sharing_base,
                 ui_paths: ui_files,
                 is_normal_ws,
                 is_normal_ignore,
                 is_battle_ws,
                 is_battle_ignore,
+                tags,
             }));
         }
 
         if !alts.is_empty() {
             let mut folder_lookup = HashMap::new();
             folder_lookup.insert(
-                Hash40::from("effect/stage").join_path(stage_path.file_name.hash40()),
-                Hash40::from("effect/stage").join_path(stage_path.file_name.hash40()),
+                Hash40::from("effect/stage").join_path_labeled(stage_path.file_name.hash40()),
+                Hash40::from("effect/stage").join_path_labeled(stage_path.file_name.hash40()),
             );
 
             let ui_files = get_ui_files(stage_path.file_name.hash40(), 0);
             let folders = collect_folders(search, normal_path.path.hash40(), Hash40::from(""));
 
-            folder_lookup.extend(folders.into_iter().map(|path| {
-                (
-                    normal_path.path.hash40().join_path(path),
-                    normal_path.path.hash40().join_path(path),
-                )
-            }));
+            let normal_folders: HashSet<Hash40> = folders
+                .into_iter()
+                .map(|path| normal_path.path.hash40().join_path_labeled(path))
+                .collect();
+            folder_lookup.extend(normal_folders.iter().map(|path| (*path, *path)));
 
+            let mut battle_folders = HashSet::new();
             if let Some(battle_path) = battle_path {
                 let folders = collect_folders(search, battle_path.path.hash40(), Hash40::from(""));
 
-                folder_lookup.extend(folders.into_iter().map(|path| {
-                    (
-                        battle_path.path.hash40().join_path(path),
-                        battle_path.path.hash40().join_path(path),
-                    )
-                }));
+                battle_folders.extend(
+                    folders
+                        .into_iter()
+                        .map(|path| battle_path.path.hash40().join_path_labeled(path)),
+                );
+                folder_lookup.extend(battle_folders.iter().map(|path| (*path, *path)));
             }
 
             alts.insert(
                 0,
                 std::sync::Arc::new(StageAlt {
                     alt_folders: folder_lookup,
+                    normal_folders,
+                    battle_folders,
                     sharing_base: HashMap::new(),
                     ui_paths: ui_files,
                     is_normal_ws: true,
                     is_normal_ignore: false,
                     is_battle_ws: true,
                     is_battle_ignore: false,
+                    tags: HashSet::new(),
                 }),
             );
         }
 
         let stage_name = stage_path.file_name.hash40();
+        StageAltManager::register_vanilla_ui_labels(stage_name);
 
         let mut alt_info = StageAltInfo {
             stage_name,
             stage_folder: stage_path.path.hash40(),
-            normal_folder: stage_path.path.hash40().join_path("normal"),
-            battle_folder: stage_path.path.hash40().join_path("battle"),
+            normal_folder: stage_path.path.hash40().join_path_labeled("normal"),
+            battle_folder: stage_path.path.hash40().join_path_labeled("battle"),
             alts_found: vec![],
         };
 
@@ -739,7 +762,23 @@ pub fn collect_alts() {
         alt_infos.insert(alt_info.stage_name, alt_info);
     }
 
+    let stages_with_alts = alt_infos
+        .values()
+        .filter(|info: &&StageAltInfo| !info.alts_found.is_empty())
+        .count();
+    let alt_count = alt_infos
+        .values()
+        .map(|info| info.alts_found.len().saturating_sub(1))
+        .sum();
+    crate::logger::log_alt_count(stages_with_alts, alt_count);
+
+    let result_stage_count = alt_infos
+        .get(&Hash40::from("resultstage"))
+        .map_or(0, |info: &StageAltInfo| info.alts_found.len());
+    info!("Discovered {} alts for the result stage", result_stage_count.saturating_sub(1));
+
     let mut mgr = crate::alts::get_mut();
     mgr.alt_infos = alt_infos;
     mgr.alts = total_alts;
+    mgr.resolve_config();
 }
@@ -1,12 +1,16 @@
 use std::{
+    mem::ManuallyDrop,
     ops::{Index, IndexMut},
     sync::atomic::{AtomicU32, Ordering},
 };
 
-use skyline::{nn, hooks::RuntimeStaticWrapper};
+use skyline::nn;
 use smash_arc::{LoadedArc, LoadedSearchSection};
 
-use super::containers::{CppVector, ResList};
+use super::{
+    containers::{CppVector, ResList},
+    offsets,
+};
 
 #[repr(u8)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -73,19 +77,49 @@ impl Clone for LoadedData {
     }
 }
 
-#[derive(Debug)]
+/// The vectors in here are owned by the game, so they are wrapped in `ManuallyDrop` to make sure that Rust never frees
+/// them if one of these ends up being dropped.
 #[repr(C)]
+#[derive(Debug)]
 pub struct LoadedDirectory {
     pub file_group_index: u32,
     pub ref_count: AtomicU32,
     pub flags: u8,
     pub state: LoadState,
     pub incoming_request_count: AtomicU32, // note, could be wrong
-    pub child_path_indices: CppVector<u32>,
-    pub child_folders: CppVector<*mut LoadedDirectory>,
+    pub child_path_indices: ManuallyDrop<CppVector<u32>>,
+    pub child_folders: ManuallyDrop<CppVector<*mut LoadedDirectory>>,
     pub redirection_directory: *mut LoadedDirectory,
 }
 
+impl LoadedDirectory {
+    /// Iterates over the loaded child directories, skipping any that have not been allocated
+    pub fn child_directories(&self) -> impl Iterator<Item = &LoadedDirectory> {
+        self.child_folders
+            .iter()
+            .filter(|dir| !dir.is_null())
+            .map(|dir| unsafe { &**dir })
+    }
+
+    /// Iterates over the loaded child directories mutably, skipping any that have not been allocated
+    pub fn child_directories_mut(&mut self) -> impl Iterator<Item = &mut LoadedDirectory> {
+        self.child_folders
+            .iter_mut()
+            .filter(|dir| !dir.is_null())
+            .map(|dir| unsafe { &mut **dir })
+    }
+
+    /// Gets the directory that this one redirects to, if there is one
+    pub fn redirection(&self) -> Option<&LoadedDirectory> {
+        unsafe { self.redirection_directory.as_ref() }
+    }
+
+    /// Gets the directory that this one redirects to mutably, if there is one
+    pub fn redirection_mut(&mut self) -> Option<&mut LoadedDirectory> {
+        unsafe { self.redirection_directory.as_mut() }
+    }
+}
+
 #[repr(C)]
 pub struct PathInformation {
     pub arc: &'static mut LoadedArc,
@@ -101,11 +135,11 @@ pub struct FilesystemInfo {
     pub loaded_data_len: u32,
     pub loaded_filepath_count: u32,
     pub loaded_data_count: u32,
-    pub loaded_filepath_list: CppVector<u32>,
+    pub loaded_filepath_list: ManuallyDrop<CppVector<u32>>,
     pub loaded_directories: *const LoadedDirectory,
     pub loaded_directory_len: u32,
     pub unk: u32,
-    pub unk2: CppVector<u32>,
+    pub unk2: ManuallyDrop<CppVector<u32>>,
     pub unk3: u8,
     pub unk4: [u8; 7],
     pub addr: *const (),
@@ -115,19 +149,11 @@ pub struct FilesystemInfo {
 
 impl FilesystemInfo {
     pub fn instance() -> Option<&'static Self> {
-        if !FS_INFO.is_null() {
-            Some(unsafe { &**RuntimeStaticWrapper::get_addr(&FS_INFO) })
-        } else {
-            None
-        }
+        unsafe { fs_info().as_ref() }
     }
 
     pub fn instance_mut() -> Option<&'static mut Self> {
-        if !FS_INFO.is_null() {
-            Some(unsafe { &mut **RuntimeStaticWrapper::get_addr(&FS_INFO) })
-        } else {
-            None
-        }
+        unsafe { fs_info().as_mut() }
     }
 
     pub fn arc(&self) -> &LoadedArc {
@@ -238,19 +264,11 @@ pub struct ResServiceNX {
 
 impl ResServiceNX {
     pub fn instance() -> Option<&'static Self> {
-        if !RES_SERVICE.is_null() {
-            Some(unsafe { &**RuntimeStaticWrapper::get_addr(&RES_SERVICE) })
-        } else {
-            None
-        }
+        unsafe { res_service().as_ref() }
     }
 
     pub fn instance_mut() -> Option<&'static mut Self> {
-        if !RES_SERVICE.is_null() {
-            Some(unsafe { &mut **RuntimeStaticWrapper::get_addr(&RES_SERVICE) })
-        } else {
-            None
-        }
+        unsafe { res_service().as_mut() }
     }
 }
 
@@ -283,8 +301,10 @@ impl IndexMut<&LoadedFilepath> for [LoadedData] {
     }
 }
 
-#[skyline::from_offset_static(0x5330f20)]
-static FS_INFO: *mut FilesystemInfo;
+unsafe fn fs_info() -> *mut FilesystemInfo {
+    *(offsets::text_addr(offsets::fs_info()) as *const *mut FilesystemInfo)
+}
 
-#[skyline::from_offset_static(0x5330f28)]
-static RES_SERVICE: *mut ResServiceNX;
\ No newline at end of file
+unsafe fn res_service() -> *mut ResServiceNX {
+    *(offsets::text_addr(offsets::res_service()) as *const *mut ResServiceNX)
+}
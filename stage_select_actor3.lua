@@ -484,6 +484,24 @@ local set_stage_preview_buttons_enable = function(preview_index, enable)
     preview:set_decidable(button_id + 1, enable)
 end
 
+local set_alt_preview = function(preview_index, panel_id, stage_form, alt_id) 
+    local preview = stage_previews[preview_index + 1]
+
+    preview.alt_id_ = alt_id
+    local texture_idx = StageAltManager.get_index_for_texture(panel_id, stage_form, alt_id)
+    local parts_name = get_stage_preview_name(preview_index)
+    local parts = root_view:get_parts(parts_name)
+    local pane_name = "set_rep_stage"
+    if stage_form == STAGE_FORM_TYPE_BATTLE then
+        pane_name = "set_rep_stage_battle"
+    elseif stage_form == STAGE_FORM_TYPE_END then
+        pane_name = "set_rep_stage_end"
+    end
+
+    local texture_pane = parts:get_pane(pane_name)
+    texture_pane:replace_texture(texture_idx)
+end
+
 -- Sets the stage preview based on the selected stage panel
 -- CLOSURE_26, R86
 local set_stage_preview_from_stage_panel = function(preview_index, panel_index)
@@ -495,6 +513,14 @@ local set_stage_preview_from_stage_panel = function(preview_index, panel_index)
 
     UiScriptPlayer.invoke("set_stage_preview_from_panel", preview_index, panel_index)
     set_stage_preview_form(preview_index, stage_previews[preview_index + 1].form_type_)
+
+    if panel_index ~= UI_INVALID_INDEX then
+        local form = stage_previews[preview_index + 1].form_type_
+        local default_alt = StageAltManager.get_default_alt(panel_index, form)
+        if default_alt ~= 0 then
+            set_alt_preview(preview_index, panel_index, form, default_alt)
+        end
+    end
 end
 
 -- Sets the stage preview based on the selected custom stage panel
@@ -507,24 +533,6 @@ local set_stage_preview_from_sub_stage_panel = function(preview_index, panel_ind
     UiScriptPlayer.invoke("set_stage_preview_from_sub_panel", preview_index, panel_index)
 end
 
-local set_alt_preview = function(preview_index, panel_id, stage_form, alt_id) 
-    local preview = stage_previews[preview_index + 1]
-
-    preview.alt_id_ = alt_id
-    local texture_idx = StageAltManager.get_index_for_texture(panel_id, stage_form, alt_id)
-    local parts_name = get_stage_preview_name(preview_index)
-    local parts = root_view:get_parts(parts_name)
-    local pane_name = "set_rep_stage"
-    if stage_form == STAGE_FORM_TYPE_BATTLE then
-        pane_name = "set_rep_stage_battle"
-    elseif stage_form == STAGE_FORM_TYPE_END then
-        pane_name = "set_rep_stage_end"
-    end
-
-    local texture_pane = parts:get_pane(pane_name)
-    texture_pane:replace_texture(texture_idx)
-end
-
 -- Plays the stage form switch animation
 -- CLOSURE_28, R88
 local switch_stage_form = function(preview_index, stage_form)
@@ -542,10 +550,10 @@ local switch_stage_form = function(preview_index, stage_form)
     if preview.alt_id_ ~= 0 then
         if preview.panel_id_ == UI_INVALID_INDEX then
             StageAltManager.send_message("invalid index")
-            next_alt = StageAltManager.get_next_alt(current_selected_panel, preview.alt_id_ - 1, stage_form)
+            next_alt = StageAltManager.resolve_alt_for_form(current_selected_panel, preview.alt_id_, stage_form)
         else
             StageAltManager.send_message("valid index")
-            next_alt = StageAltManager.get_next_alt(preview.panel_id_, preview.alt_id_ - 1, stage_form)
+            next_alt = StageAltManager.resolve_alt_for_form(preview.panel_id_, preview.alt_id_, stage_form)
         end
     end
     if preview.panel_id_ == UI_INVALID_INDEX then
@@ -1446,6 +1454,17 @@ local open_bgm_select_by_button = function(button_id)
     return false
 end
 
+-- Gets the ids of the panels that the random pool can pick from, which are the panels that aren't locked
+local get_random_pool_panels = function()
+    local panels = {}
+    for i=0, STAGE_PANEL_LIST_NUM - 1, 1 do
+        if UiScriptPlayer.invoke("is_lock_stage_panel", i) == false then
+            panels[#panels + 1] = i
+        end
+    end
+    return panels
+end
+
 -- CLOSURE_67, R127
 local decide_normal_stage = function()
     if check_all_previews_enabled() == true then
@@ -1463,6 +1482,13 @@ local decide_normal_stage = function()
         return false
     end
 
+    -- When the random pool is made of alts, the stage is picked here and used in place of the random panel
+    local random_panel, random_alt = StageAltManager.pick_random_stage(current_selected_panel, stage_previews[current_selected_preview + 1].form_type_, get_random_pool_panels())
+    if random_panel ~= nil then
+        current_selected_panel = random_panel
+        stage_previews[current_selected_preview + 1].alt_id_ = random_alt
+    end
+
     UiScriptPlayer.invoke("set_medal_visible", current_selected_preview, true)
     UiScriptPlayer.invoke("set_medal_collect_range_from_panel", current_selected_preview, current_selected_panel)
     if UiScriptPlayer.invoke("is_hand_interpolated_moving") == false then
@@ -1480,7 +1506,7 @@ local decide_normal_stage = function()
 
     play_decide_stage_animation(current_selected_preview)
 
-    StageAltManager.register_alt(current_selected_preview, current_selected_panel, stage_previews[current_selected_preview + 1].alt_id_)
+    StageAltManager.register_alt(current_selected_preview, current_selected_panel, stage_previews[current_selected_preview + 1].alt_id_, stage_previews[current_selected_preview + 1].form_type_)
 
     return true
 end
@@ -0,0 +1,115 @@
+//! Compares two alt registry dumps written by stage-alts (`alt_dump.json`) and prints what changed.
+//!
+//! Usage: `alt-dump-diff <old.json> <new.json>`
+
+use std::process::ExitCode;
+
+use serde_json::Value;
+
+fn read_dump(path: &str) -> Result<Value, String> {
+    let data = std::fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path, e))?;
+    serde_json::from_str(&data).map_err(|e| format!("failed to parse {}: {}", path, e))
+}
+
+/// Recursively compares the two values, pushing a line for every difference that was found
+fn diff(path: &str, old: &Value, new: &Value, out: &mut Vec<String>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, old_value) in old.iter() {
+                let child = format!("{}/{}", path, key);
+                match new.get(key) {
+                    Some(new_value) => diff(&child, old_value, new_value, out),
+                    None => out.push(format!("- {}", child)),
+                }
+            }
+
+            for key in new.keys().filter(|key| !old.contains_key(*key)) {
+                out.push(format!("+ {}/{}", path, key));
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for (index, (old_value, new_value)) in old.iter().zip(new.iter()).enumerate() {
+                diff(&format!("{}[{}]", path, index), old_value, new_value, out);
+            }
+
+            for index in new.len()..old.len() {
+                out.push(format!("- {}[{}]", path, index));
+            }
+
+            for index in old.len()..new.len() {
+                out.push(format!("+ {}[{}]", path, index));
+            }
+        }
+        (old, new) if old != new => out.push(format!("~ {}: {} -> {}", path, old, new)),
+        _ => {}
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() != 3 {
+        eprintln!("usage: {} <old.json> <new.json>", args[0]);
+        return ExitCode::from(2);
+    }
+
+    let (old, new) = match (read_dump(&args[1]), read_dump(&args[2])) {
+        (Ok(old), Ok(new)) => (old, new),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("{}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut out = vec![];
+    diff("", &old["stages"], &new["stages"], &mut out);
+
+    if out.is_empty() {
+        println!("The dumps are identical");
+        return ExitCode::SUCCESS;
+    }
+
+    for line in out {
+        println!("{}", line);
+    }
+
+    ExitCode::FAILURE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> Value {
+        read_dump(&format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+    }
+
+    #[test]
+    fn diff_fixtures() {
+        let old = fixture("old.json");
+        let new = fixture("new.json");
+
+        let mut out = vec![];
+        diff("", &old["stages"], &new["stages"], &mut out);
+
+        // the version is not part of the comparison
+        let expected = [
+            "~ /battlefield/alts[1]/flags/is_normal_ws: false -> true",
+            "+ /battlefield/alts[1]/folders/stage/battlefield/normal/light",
+            "- /battlefield/alts[1]/sharing_base/stage/battlefield/normal/model/bf_main.nutexb",
+            "+ /battlefield/alts[1]/tags[1]",
+            "+ /battlefield/alts[2]",
+            "- /fd",
+            "+ /pokemon_stadium",
+        ];
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn identical_dumps_have_no_differences() {
+        let old = fixture("old.json");
+
+        let mut out = vec![];
+        diff("", &old["stages"], &old["stages"], &mut out);
+        assert!(out.is_empty(), "{:?}", out);
+    }
+}